@@ -0,0 +1,86 @@
+use crate::{util, Database, HashMap};
+use axum::{
+    extract::Path,
+    response::IntoResponse,
+    routing::{get, post},
+    Extension, Json, Router,
+};
+
+/// the reserved path prefix of the admin routes
+pub const ADMIN_PATH: &str = "/__admin";
+
+/// the paths of the admin routes under the `ADMIN_PATH`
+pub const ROUTES: [&str; 6] = [
+    "/reset",
+    "/reset/:data",
+    "/snapshots",
+    "/snapshots/:name",
+    "/snapshots/:name/restore",
+    "/export",
+];
+
+/// create the admin routes to manage the data at runtime
+///
+/// - `POST /__admin/reset` reset all of the collections from the templates
+/// - `POST /__admin/reset/:data` reset the given collection
+/// - `GET /__admin/snapshots` list the snapshot names
+/// - `POST /__admin/snapshots/:name` take a snapshot of the current collections
+/// - `DELETE /__admin/snapshots/:name` delete the snapshot
+/// - `POST /__admin/snapshots/:name/restore` restore the collections from the snapshot
+/// - `GET /__admin/export` export the current collections as a config file
+pub fn create() -> Router {
+    Router::new()
+        .route(&format!("{ADMIN_PATH}/reset"), post(reset_all))
+        .route(&format!("{ADMIN_PATH}/reset/:data"), post(reset_data))
+        .route(&format!("{ADMIN_PATH}/snapshots"), get(list_snapshots))
+        .route(
+            &format!("{ADMIN_PATH}/snapshots/:name"),
+            post(take_snapshot).delete(delete_snapshot),
+        )
+        .route(
+            &format!("{ADMIN_PATH}/snapshots/:name/restore"),
+            post(restore_snapshot),
+        )
+        .route(&format!("{ADMIN_PATH}/export"), get(export))
+}
+
+async fn reset_all(Extension(db): Extension<Database>) -> impl IntoResponse {
+    util::wrap_result(db.reset(None), None)
+}
+
+async fn reset_data(
+    Path(path_map): Path<HashMap<String, String>>,
+    Extension(db): Extension<Database>,
+) -> impl IntoResponse {
+    util::wrap_result(db.reset(Some(&path_map["data"])), None)
+}
+
+async fn list_snapshots(Extension(db): Extension<Database>) -> impl IntoResponse {
+    util::wrap_result(db.snapshot_names(), None)
+}
+
+async fn take_snapshot(
+    Path(path_map): Path<HashMap<String, String>>,
+    Extension(db): Extension<Database>,
+) -> impl IntoResponse {
+    util::wrap_result(db.take_snapshot(&path_map["name"]), None)
+}
+
+async fn restore_snapshot(
+    Path(path_map): Path<HashMap<String, String>>,
+    Extension(db): Extension<Database>,
+) -> impl IntoResponse {
+    util::wrap_result(db.restore_snapshot(&path_map["name"]), None)
+}
+
+async fn delete_snapshot(
+    Path(path_map): Path<HashMap<String, String>>,
+    Extension(db): Extension<Database>,
+) -> impl IntoResponse {
+    util::wrap_result(db.delete_snapshot(&path_map["name"]), None)
+}
+
+/// the exported config is returned without wrapping so it can be saved directly
+async fn export(Extension(db): Extension<Database>) -> impl IntoResponse {
+    Json(db.export())
+}
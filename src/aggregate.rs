@@ -0,0 +1,140 @@
+use crate::{models::NamedQuery, util};
+use serde_json::{json, Map, Value};
+
+/// aggregate the items by the query,
+/// the `_group_by` returns the summary of each group, otherwise the summary of all items
+pub fn aggregate(items: &[&Value], q: &NamedQuery) -> Value {
+    let field = match &q.group_by {
+        Some(field) => field,
+        None => return Value::Object(summary(items, q)),
+    };
+
+    // the groups are ordered by the key
+    let mut groups: Vec<(Value, Vec<&Value>)> = Vec::new();
+    for item in items {
+        let key = util::get_path(item, field).cloned().unwrap_or(Value::Null);
+        match groups.iter_mut().find(|(k, _)| k == &key) {
+            Some((_, group)) => group.push(item),
+            None => groups.push((key, vec![item])),
+        }
+    }
+    groups.sort_by(|a, b| util::cmp(&a.0, &b.0));
+
+    let groups = groups
+        .into_iter()
+        .map(|(key, group)| {
+            let mut obj = summary(&group, q);
+            obj.insert(field.clone(), key);
+            Value::Object(obj)
+        })
+        .collect();
+
+    Value::Array(groups)
+}
+
+/// the metric function of the field values
+type Metric = fn(&[&Value]) -> Value;
+
+/// the count, metrics and facets of the items
+fn summary(items: &[&Value], q: &NamedQuery) -> Map<String, Value> {
+    let mut obj = Map::new();
+    obj.insert("count".to_string(), json!(items.len()));
+
+    let metrics: [(&str, &Vec<String>, Metric); 4] = [
+        ("sum", &q.sum, sum),
+        ("avg", &q.avg, avg),
+        ("min", &q.min, min),
+        ("max", &q.max, max),
+    ];
+    for (name, fields, f) in metrics {
+        if fields.is_empty() {
+            continue;
+        }
+
+        let mut values = Map::new();
+        for field in fields {
+            let list: Vec<&Value> = items
+                .iter()
+                .filter_map(|item| util::get_path(item, field))
+                .filter(|v| !v.is_null())
+                .collect();
+            values.insert(field.clone(), f(&list));
+        }
+        obj.insert(name.to_string(), Value::Object(values));
+    }
+
+    if !q.facet.is_empty() {
+        let mut facets = Map::new();
+        for field in &q.facet {
+            facets.insert(field.clone(), facet(items, field));
+        }
+        obj.insert("facets".to_string(), Value::Object(facets));
+    }
+
+    obj
+}
+
+/// sum the numbers, keep the integer if all of the numbers are integers
+fn sum(values: &[&Value]) -> Value {
+    let numbers: Vec<&Value> = values.iter().copied().filter(|v| v.is_number()).collect();
+    if numbers.iter().all(|v| v.is_i64()) {
+        if let Some(sum) = numbers
+            .iter()
+            .try_fold(0_i64, |acc, v| acc.checked_add(v.as_i64().unwrap()))
+        {
+            return json!(sum);
+        }
+    }
+
+    json!(numbers.iter().filter_map(|v| v.as_f64()).sum::<f64>())
+}
+
+/// the average of the numbers, null if there is no number
+fn avg(values: &[&Value]) -> Value {
+    let numbers: Vec<f64> = values.iter().filter_map(|v| v.as_f64()).collect();
+    if numbers.is_empty() {
+        return Value::Null;
+    }
+
+    json!(numbers.iter().sum::<f64>() / numbers.len() as f64)
+}
+
+fn min(values: &[&Value]) -> Value {
+    values
+        .iter()
+        .min_by(|a, b| util::cmp(a, b))
+        .map_or(Value::Null, |v| (*v).clone())
+}
+
+fn max(values: &[&Value]) -> Value {
+    values
+        .iter()
+        .max_by(|a, b| util::cmp(a, b))
+        .map_or(Value::Null, |v| (*v).clone())
+}
+
+/// count the distinct values of the field, the array counts each element,
+/// ordered by the count desc and then the value
+fn facet(items: &[&Value], field: &str) -> Value {
+    let mut counts: Vec<(Value, usize)> = Vec::new();
+    for item in items {
+        let values = match util::get_path(item, field) {
+            Some(Value::Array(arr)) => arr.iter().collect(),
+            Some(v) => vec![v],
+            None => vec![],
+        };
+
+        for value in values {
+            match counts.iter_mut().find(|(v, _)| v == value) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((value.clone(), 1)),
+            }
+        }
+    }
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| util::cmp(&a.0, &b.0)));
+
+    counts
+        .into_iter()
+        .map(|(value, count)| json!({ "value": value, "count": count }))
+        .collect()
+}
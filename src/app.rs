@@ -1,4 +1,5 @@
 use crate::{
+    admin,
     models::{DataConfig, RoutingRule, RoutingValue, Wrapper, WRAP_KEY_ERR, WRAP_KEY_OK},
     service::*,
     util, Database, HashMap,
@@ -11,32 +12,156 @@ use axum::{
     routing::{get, get_service},
     Extension, Form, Json, Router,
 };
+use once_cell::sync::Lazy;
+use regex::Regex;
 use serde_json::Value;
-use tower::service_fn;
+use std::sync::{Arc, Mutex};
+use tower::{service_fn, ServiceExt};
 use tower_http::services::ServeDir;
 
-const DATA_QUERY_TPL: &str = "/api/([^/]*)$";
-const DATA_ID_TPL: &str = "/api/([^/]*)/([^/]*)$";
-const FILE_ID_TPL: &str = "/file/([^/]*)$";
+/// the paths of the built-in routes
+const ROUTES: [&str; 5] = [
+    "/api/:data",
+    "/api/:data/:id",
+    "/api/:data/:id/:child",
+    "/api/:data/:id/:child/:child_id",
+    "/file/:id",
+];
 
-pub fn create(_: &Database) -> Router {
-    let app = Router::new()
-        .route("/api/:data", get(query_data).post(query_data))
+static DATA_QUERY_TPL: Lazy<Regex> = Lazy::new(|| Regex::new("/api/([^/]*)$").unwrap());
+static DATA_ID_TPL: Lazy<Regex> = Lazy::new(|| Regex::new("/api/([^/]*)/([^/]*)$").unwrap());
+static CHILD_QUERY_TPL: Lazy<Regex> =
+    Lazy::new(|| Regex::new("/api/([^/]*)/([^/]*)/([^/]*)$").unwrap());
+static CHILD_ID_TPL: Lazy<Regex> =
+    Lazy::new(|| Regex::new("/api/([^/]*)/([^/]*)/([^/]*)/([^/]*)$").unwrap());
+static FILE_ID_TPL: Lazy<Regex> = Lazy::new(|| Regex::new("/file/([^/]*)$").unwrap());
+
+/// the built-in route which the routing `to` points to
+#[derive(Debug, PartialEq, Eq)]
+pub enum Target<'a> {
+    Data(&'a str),
+    Item(&'a str, &'a str),
+    /// the children of the parent item, `/api/:data/:id/:child`
+    Children(&'a str, &'a str, &'a str),
+    /// the child of the parent item, `/api/:data/:id/:child/:child_id`
+    Child(&'a str, &'a str, &'a str, &'a str),
+    File(&'a str),
+}
+
+/// match the routing `to` with the built-in routes
+pub fn parse_target(to: &str) -> Option<Target<'_>> {
+    if let Some(cap) = DATA_QUERY_TPL.captures(to) {
+        return Some(Target::Data(cap.get(1).unwrap().as_str()));
+    }
+
+    if let Some(cap) = DATA_ID_TPL.captures(to) {
+        return Some(Target::Item(
+            cap.get(1).unwrap().as_str(),
+            cap.get(2).unwrap().as_str(),
+        ));
+    }
+
+    if let Some(cap) = CHILD_QUERY_TPL.captures(to) {
+        return Some(Target::Children(
+            cap.get(1).unwrap().as_str(),
+            cap.get(2).unwrap().as_str(),
+            cap.get(3).unwrap().as_str(),
+        ));
+    }
+
+    if let Some(cap) = CHILD_ID_TPL.captures(to) {
+        return Some(Target::Child(
+            cap.get(1).unwrap().as_str(),
+            cap.get(2).unwrap().as_str(),
+            cap.get(3).unwrap().as_str(),
+            cap.get(4).unwrap().as_str(),
+        ));
+    }
+
+    if let Some(cap) = FILE_ID_TPL.captures(to) {
+        return Some(Target::File(cap.get(1).unwrap().as_str()));
+    }
+
+    None
+}
+
+/// the app router which can be replaced while the server is running
+#[derive(Clone)]
+pub struct AppRouter(Arc<Mutex<Router>>);
+
+impl AppRouter {
+    pub fn new(db: &Database) -> Result<Self, String> {
+        let router = build(&db.get_config())?;
+        Ok(AppRouter(Arc::new(Mutex::new(router))))
+    }
+
+    /// replace the routes with the new built one
+    pub fn replace(&self, router: Router) {
+        *self.0.lock().unwrap() = router;
+    }
+
+    /// the service forwards every request to the current router
+    pub fn service(&self) -> Router {
+        let current = self.0.clone();
+        Router::new().fallback(service_fn(move |req: Request<Body>| {
+            let router = current.lock().unwrap().clone();
+            router.oneshot(req)
+        }))
+    }
+}
+
+/// build the built-in routes together with the admin routes and config routing,
+/// the conflicting routes are returned as error instead of panic
+pub fn build(config: &DataConfig) -> Result<Router, String> {
+    check_routes(config).map_err(|err| format!("invalid routing: {}", err))?;
+    Ok(create(config).merge(admin::create()).merge(proxy(config)))
+}
+
+/// check the routing paths against the built-in and admin routes the same way the router does
+fn check_routes(config: &DataConfig) -> Result<(), String> {
+    let mut router = matchit::Router::new();
+    let admin = admin::ROUTES.map(|path| format!("{}{}", admin::ADMIN_PATH, path));
+    for path in ROUTES
+        .iter()
+        .copied()
+        .chain(admin.iter().map(String::as_str))
+    {
+        router.insert(path, ()).map_err(|err| err.to_string())?;
+    }
+
+    for key in config.routing.keys() {
+        if !key.starts_with('/') {
+            return Err(format!("{}: paths must start with a `/`", key));
+        }
+        router
+            .insert(key.as_str(), ())
+            .map_err(|err| format!("{}: {}", key, err))?;
+    }
+
+    Ok(())
+}
+
+pub fn create(config: &DataConfig) -> Router {
+    // the built-in routes use the default wrapping
+    let wrap: Wrapper = config.wrapping.clone();
+    Router::new()
+        .route("/api/:data", get(query_data).post(create_data))
         .route(
             "/api/:data/:id",
             get(get_data)
                 .post(post_data)
                 .put(put_data)
+                .patch(patch_data)
                 .delete(delete_data),
         )
+        .route("/api/:data/:id/:child", get(query_data).post(create_data))
+        .route("/api/:data/:id/:child/:child_id", get(get_data))
         .route("/file/:id", get(get_file).post(get_file))
-        .fallback(get_service(ServeDir::new("static")).handle_error(handle_error));
-
-    app
+        .fallback(get_service(ServeDir::new("static")).handle_error(handle_error))
+        .layer(Extension(wrap))
 }
 
-pub fn proxy(db: &Database) -> Router {
-    let config = db.get_config();
+pub fn proxy(config: &DataConfig) -> Router {
     let mut router = Router::new();
     for (key, v) in &config.routing {
         tracing::debug!("routing {} to {:?}", key, v);
@@ -51,12 +176,12 @@ pub fn proxy(db: &Database) -> Router {
                     let wrap = Extension(ex.get::<Wrapper>().cloned().unwrap());
                     let Extension(routing_value) =
                         Extension(ex.get::<RoutingValue>().cloned().unwrap());
-                    let uri = req.uri();
+                    let uri = req.uri().clone();
                     tracing::info!("uri={}", uri);
 
                     let mut req_parts = RequestParts::new(req);
 
-                    let query = Query::<HashMap<String, String>>::from_request(&mut req_parts)
+                    let query = Query::<Vec<(String, String)>>::from_request(&mut req_parts)
                         .await
                         .unwrap();
                     let mut path = Path::<HashMap<String, String>>::from_request(&mut req_parts)
@@ -83,44 +208,59 @@ pub fn proxy(db: &Database) -> Router {
                     }
 
                     let method = req_parts.method();
+                    let headers = req_parts.headers().clone();
                     // 检查规则
                     if let Err(err) = validate_rules(routing_value.rules, &path, &query, &body) {
                         let res = util::wrap_result(Err(err), Some(wrap.0)).into_response();
                         return Ok(res);
                     }
                     // match the template
-                    let re = regex::Regex::new(DATA_QUERY_TPL).unwrap();
-                    if let Some(cap) = re.captures(&routing_value.to) {
+                    if let Some(cap) = DATA_QUERY_TPL.captures(&routing_value.to) {
                         let data = cap.get(1).unwrap().as_str();
-                        if !data.starts_with(":") {
+                        if !data.starts_with(':') {
                             path.insert("data".to_string(), data.to_string());
                         }
 
-                        let new_query = create_query(query, routing_value.query);
-                        let res = query_data(path, Query(new_query), db.clone())
-                            .await
-                            .into_response();
+                        let mut res = match *method {
+                            Method::POST => create_data(path, body, db, wrap).await,
+                            _ => {
+                                let page_key = page_key(routing_value.query.as_ref());
+                                let new_query =
+                                    create_query(query, routing_value.query, &routing_value.select);
+                                query_page(&path, &new_query, &db, &uri, &page_key)
+                            }
+                        };
+
+                        if let Some(code) = routing_value.status {
+                            *res.status_mut() = StatusCode::from_u16(code).unwrap()
+                        }
+
                         return Ok(res);
                     }
 
-                    if let Some(cap) = regex::Regex::new(DATA_ID_TPL)
-                        .unwrap()
-                        .captures(&routing_value.to)
-                    {
+                    if let Some(cap) = DATA_ID_TPL.captures(&routing_value.to) {
                         let data = cap.get(1).unwrap().as_str();
                         let id = cap.get(2).unwrap().as_str();
-                        if !data.starts_with(":") {
+                        if !data.starts_with(':') {
                             path.insert("data".to_string(), data.to_string());
                         }
 
-                        if !id.starts_with(":") {
+                        if !id.starts_with(':') {
                             path.insert("id".to_string(), id.to_string());
                         }
-                        let mut res = match method {
-                            &Method::GET => get_data(path, db, wrap).await.into_response(),
-                            &Method::POST => post_data(path, body, db, wrap).await.into_response(),
-                            &Method::PUT => put_data(path, body, db, wrap).await.into_response(),
-                            &Method::DELETE => delete_data(path, db, wrap).await.into_response(),
+                        let query = Query(create_query(
+                            query,
+                            routing_value.query,
+                            &routing_value.select,
+                        ));
+                        let mut res = match *method {
+                            Method::GET => get_data(path, query, db, wrap).await.into_response(),
+                            Method::POST => post_data(path, body, db, wrap).await.into_response(),
+                            Method::PUT => put_data(path, body, db, wrap).await.into_response(),
+                            Method::PATCH => patch_data(path, headers, body, db, wrap)
+                                .await
+                                .into_response(),
+                            Method::DELETE => delete_data(path, db, wrap).await.into_response(),
                             _ => (StatusCode::METHOD_NOT_ALLOWED, "method not support")
                                 .into_response(),
                         };
@@ -132,17 +272,77 @@ pub fn proxy(db: &Database) -> Router {
                         return Ok(res);
                     }
 
-                    if let Some(cap) = regex::Regex::new(FILE_ID_TPL)
-                        .unwrap()
-                        .captures(&routing_value.to)
-                    {
+                    if let Some(cap) = CHILD_QUERY_TPL.captures(&routing_value.to) {
+                        for (i, key) in ["data", "id", "child"].iter().enumerate() {
+                            // the `:param` takes the value from the request path
+                            let value = cap.get(i + 1).unwrap().as_str();
+                            let value = match value.strip_prefix(':') {
+                                Some(name) => path.get(name).cloned(),
+                                None => Some(value.to_string()),
+                            };
+                            if let Some(value) = value {
+                                path.insert(key.to_string(), value);
+                            }
+                        }
+
+                        let mut res = match *method {
+                            Method::GET => {
+                                let page_key = page_key(routing_value.query.as_ref());
+                                let new_query =
+                                    create_query(query, routing_value.query, &routing_value.select);
+                                query_page(&path, &new_query, &db, &uri, &page_key)
+                            }
+                            Method::POST => create_data(path, body, db, wrap).await,
+                            _ => (StatusCode::METHOD_NOT_ALLOWED, "method not support")
+                                .into_response(),
+                        };
+
+                        if let Some(code) = routing_value.status {
+                            *res.status_mut() = StatusCode::from_u16(code).unwrap()
+                        }
+
+                        return Ok(res);
+                    }
+
+                    if let Some(cap) = CHILD_ID_TPL.captures(&routing_value.to) {
+                        for (i, key) in ["data", "id", "child", "child_id"].iter().enumerate() {
+                            // the `:param` takes the value from the request path
+                            let value = cap.get(i + 1).unwrap().as_str();
+                            let value = match value.strip_prefix(':') {
+                                Some(name) => path.get(name).cloned(),
+                                None => Some(value.to_string()),
+                            };
+                            if let Some(value) = value {
+                                path.insert(key.to_string(), value);
+                            }
+                        }
+
+                        let query = Query(create_query(
+                            query,
+                            routing_value.query,
+                            &routing_value.select,
+                        ));
+                        let mut res = match *method {
+                            Method::GET => get_data(path, query, db, wrap).await.into_response(),
+                            _ => (StatusCode::METHOD_NOT_ALLOWED, "method not support")
+                                .into_response(),
+                        };
+
+                        if let Some(code) = routing_value.status {
+                            *res.status_mut() = StatusCode::from_u16(code).unwrap()
+                        }
+
+                        return Ok(res);
+                    }
+
+                    if let Some(cap) = FILE_ID_TPL.captures(&routing_value.to) {
                         let id = cap.get(1).unwrap().as_str();
-                        if !id.starts_with(":") {
+                        if !id.starts_with(':') {
                             path.insert("id".to_string(), id.to_string());
                         }
-                        let mut res = match method {
-                            &Method::GET => get_file(path, db).await.into_response(),
-                            &Method::POST => get_file(path, db).await.into_response(),
+                        let mut res = match *method {
+                            Method::GET => get_file(path, db).await.into_response(),
+                            Method::POST => get_file(path, db).await.into_response(),
                             _ => (StatusCode::METHOD_NOT_ALLOWED, "method not support")
                                 .into_response(),
                         };
@@ -169,33 +369,42 @@ async fn handle_error(_: std::io::Error) -> impl IntoResponse {
     (StatusCode::INTERNAL_SERVER_ERROR, "server error")
 }
 
+/// the page key in the uri which is mapped to _page
+fn page_key(map: Option<&HashMap<String, String>>) -> String {
+    map.and_then(|q| q.iter().find(|(_, v)| v.as_str() == "_page"))
+        .map_or("_page", |(k, _)| k.as_str())
+        .to_string()
+}
+
 fn create_query(
-    Query(query): Query<HashMap<String, String>>,
+    Query(query): Query<Vec<(String, String)>>,
     map: Option<HashMap<String, String>>,
-) -> HashMap<String, String> {
-    let mut new_query = HashMap::new();
-    match map {
-        Some(q) => {
-            for key in query.keys() {
-                let value = &query[key];
-                if let Some(map_key) = q.get(key) {
-                    if map_key != "_" {
-                        new_query.insert(map_key.to_string(), value.to_string());
-                    }
-                }
-            }
-        }
-        None => {
-            new_query = query.clone();
-        }
+    select: &Option<String>,
+) -> Vec<(String, String)> {
+    let mut new_query: Vec<(String, String)> = match map {
+        Some(q) => query
+            .into_iter()
+            .filter_map(|(key, value)| match q.get(&key) {
+                Some(map_key) if map_key != "_" => Some((map_key.to_string(), value)),
+                _ => None,
+            })
+            .collect(),
+        None => query,
+    };
+
+    // the fixed selection of the routing takes precedence
+    if let Some(select) = select {
+        new_query.retain(|(key, _)| key != "_select");
+        new_query.push(("_select".to_string(), select.clone()));
     }
+
     new_query
 }
 
 fn validate_rules(
     rules: Option<Vec<RoutingRule>>,
     Path(path): &Path<HashMap<String, String>>,
-    Query(query): &Query<HashMap<String, String>>,
+    Query(query): &Query<Vec<(String, String)>>,
     Json(body): &Json<Value>,
 ) -> Result<bool, String> {
     if let Some(rules) = rules {
@@ -203,15 +412,15 @@ fn validate_rules(
             // match the path value
             if path.contains_key(&rule.key) {
                 let re = regex::Regex::new(&rule.r#match).unwrap();
-                if !re.is_match(&path.get(&rule.key).unwrap()) {
+                if !re.is_match(path.get(&rule.key).unwrap()) {
                     return Err(rule.message);
                 }
             }
 
             // match the query value
-            if query.contains_key(&rule.key) {
+            for (_, value) in query.iter().filter(|(key, _)| key == &rule.key) {
                 let re = regex::Regex::new(&rule.r#match).unwrap();
-                if !re.is_match(&query.get(&rule.key).unwrap()) {
+                if !re.is_match(value) {
                     return Err(rule.message);
                 }
             }
@@ -220,23 +429,20 @@ fn validate_rules(
             match &body {
                 Value::Array(arr) => {
                     for v in arr {
-                        match v {
-                            Value::Object(obj) => {
-                                if obj.contains_key(&rule.key) {
-                                    let re = regex::Regex::new(&rule.r#match).unwrap();
-                                    if !re.is_match(&obj.get(&rule.key).unwrap().to_string()) {
-                                        return Err(rule.message);
-                                    }
+                        if let Value::Object(obj) = v {
+                            if let Some(value) = obj.get(&rule.key) {
+                                let re = regex::Regex::new(&rule.r#match).unwrap();
+                                if !re.is_match(&value.to_string()) {
+                                    return Err(rule.message);
                                 }
                             }
-                            _ => {}
                         }
                     }
                 }
                 Value::Object(obj) => {
-                    if obj.contains_key(&rule.key) {
+                    if let Some(value) = obj.get(&rule.key) {
                         let re = regex::Regex::new(&rule.r#match).unwrap();
-                        if !re.is_match(&obj.get(&rule.key).unwrap().to_string()) {
+                        if !re.is_match(&value.to_string()) {
                             return Err(rule.message);
                         }
                     }
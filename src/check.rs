@@ -0,0 +1,347 @@
+use crate::{
+    admin::ADMIN_PATH,
+    app::{parse_target, Target},
+    models::{PageHeaders, RoutingValue, WRAP_DATA, WRAP_KEY_OK, WRAP_PAGE, WRAP_PAGE_ITEMS},
+    template, util,
+};
+use axum::http::StatusCode;
+use serde_json::{Map, Value};
+use std::fmt;
+
+/// the problem found in the config with its json path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+/// check the config json and return all of the problems
+pub fn check(json: &Value) -> Vec<Problem> {
+    let mut checker = Checker::default();
+    checker.root(json);
+    checker.problems
+}
+
+#[derive(Default)]
+struct Checker {
+    problems: Vec<Problem>,
+}
+
+/// the json path of the child key
+fn child(path: &str, key: &str) -> String {
+    let is_ident = key.chars().enumerate().all(|(i, c)| {
+        c == '_' || c == '$' || c.is_ascii_alphabetic() || (i > 0 && c.is_ascii_digit())
+    });
+
+    if is_ident && !key.is_empty() {
+        format!("{}.{}", path, key)
+    } else {
+        format!("{}['{}']", path, key.replace('\'', "\\'"))
+    }
+}
+
+/// check if the placeholder is in the value or its nested objects
+fn contains_placeholder(value: &Value, placeholder: &str) -> bool {
+    match value {
+        Value::String(s) => s == placeholder,
+        Value::Object(obj) => obj.values().any(|v| contains_placeholder(v, placeholder)),
+        _ => false,
+    }
+}
+
+impl Checker {
+    fn error(&mut self, path: &str, message: impl Into<String>) {
+        self.problems.push(Problem {
+            path: path.to_string(),
+            message: message.into(),
+        });
+    }
+
+    fn object<'a>(&mut self, path: &str, value: &'a Value) -> Option<&'a Map<String, Value>> {
+        match value {
+            Value::Object(obj) => Some(obj),
+            _ => {
+                self.error(path, "must be object type");
+                None
+            }
+        }
+    }
+
+    fn root(&mut self, json: &Value) {
+        let root = match self.object("$", json) {
+            Some(root) => root,
+            None => return,
+        };
+
+        // the literal records are the collections as well
+        let mut data = root
+            .get("data")
+            .and_then(|data| data.as_object())
+            .cloned()
+            .unwrap_or_default();
+        if let Some(records) = root.get("records") {
+            if let Some(records) = self.object("$.records", records) {
+                data.extend(records.clone());
+            }
+        }
+        let files = root
+            .get("file")
+            .and_then(|file| file.as_object())
+            .cloned()
+            .unwrap_or_default();
+
+        if let Some(config) = root.get("config") {
+            self.config("$.config", config, &data, &files);
+        }
+
+        if let Some(data) = root.get("data") {
+            if let Some(data) = self.object("$.data", data) {
+                for (key, value) in data {
+                    self.template(&child("$.data", key), key, value);
+                }
+            }
+        }
+
+        if let Some(file) = root.get("file") {
+            if let Some(file) = self.object("$.file", file) {
+                for (key, value) in file {
+                    if !value.is_string() {
+                        self.error(&child("$.file", key), "must be string type");
+                    }
+                }
+            }
+        }
+    }
+
+    fn config(
+        &mut self,
+        path: &str,
+        config: &Value,
+        data: &Map<String, Value>,
+        files: &Map<String, Value>,
+    ) {
+        let config = match self.object(path, config) {
+            Some(config) => config,
+            None => return,
+        };
+
+        if let Some(wrapping) = config.get("wrapping") {
+            self.wrapping(&child(path, "wrapping"), wrapping);
+        }
+
+        if let Some(page_headers) = config.get("page_headers") {
+            if serde_json::from_value::<PageHeaders>(page_headers.clone()).is_err() {
+                self.error(
+                    &child(path, "page_headers"),
+                    "must be one of 'body', 'headers' or 'both'",
+                );
+            }
+        }
+
+        let names: Vec<String> = data.keys().map(|key| template::parse_name(key)).collect();
+        if let Some(relations) = config.get("relations") {
+            self.relations(&child(path, "relations"), relations, &names);
+        }
+        if let Some(keys) = config.get("keys") {
+            self.keys(&child(path, "keys"), keys, &names);
+        }
+
+        let routing = match config.get("routing") {
+            Some(routing) => routing,
+            None => return,
+        };
+        let routing_path = child(path, "routing");
+        let routing = match self.object(&routing_path, routing) {
+            Some(routing) => routing,
+            None => return,
+        };
+
+        for (key, value) in routing {
+            let path = child(&routing_path, key);
+            if !key.starts_with('/') {
+                self.error(&path, "the routing path must start with '/'");
+            }
+            if key.starts_with(ADMIN_PATH) {
+                self.error(&path, "the routing path is reserved for the admin api");
+            }
+
+            let routing_value = match serde_json::from_value::<RoutingValue>(value.clone()) {
+                Ok(v) => v,
+                Err(err) => {
+                    self.error(&path, err.to_string());
+                    continue;
+                }
+            };
+
+            self.target(&child(&path, "to"), &routing_value.to, &names, files);
+
+            for (i, rule) in routing_value.rules.iter().flatten().enumerate() {
+                if let Err(err) = regex::Regex::new(&rule.r#match) {
+                    let path = format!("{}[{}].match", child(&path, "rules"), i);
+                    self.error(&path, format!("invalid regex: {}", err));
+                }
+            }
+
+            if let Some(select) = &routing_value.select {
+                if let Err(err) = util::check_select(select) {
+                    self.error(&child(&path, "select"), format!("invalid select: {}", err));
+                }
+            }
+
+            if let Some(status) = routing_value.status {
+                if StatusCode::from_u16(status).is_err() {
+                    self.error(&child(&path, "status"), "invalid status code");
+                }
+            }
+
+            if let Some(wrapping) = value.get("wrapping") {
+                self.wrapping(&child(&path, "wrapping"), wrapping);
+            }
+        }
+    }
+
+    /// the routing target must be a built-in route with the existing data or file
+    fn target(&mut self, path: &str, to: &str, names: &[String], files: &Map<String, Value>) {
+        let is_param = |name: &str| name.starts_with(':');
+        match parse_target(to) {
+            Some(Target::Data(name)) | Some(Target::Item(name, _)) => {
+                if !is_param(name) && !names.iter().any(|n| n == name) {
+                    self.error(path, format!("not found data by name '{}'", name));
+                }
+            }
+            Some(Target::Children(name, _, child)) | Some(Target::Child(name, _, child, _)) => {
+                for name in [name, child] {
+                    if !is_param(name) && !names.iter().any(|n| n == name) {
+                        self.error(path, format!("not found data by name '{}'", name));
+                    }
+                }
+            }
+            Some(Target::File(id)) => {
+                if !is_param(id) && !files.contains_key(id) {
+                    self.error(path, format!("not found file by id '{}'", id));
+                }
+            }
+            None => self.error(
+                path,
+                format!(
+                    "'{}' is not a built-in route, expect `/api/:data`, `/api/:data/:id`, `/api/:data/:id/:child`, `/api/:data/:id/:child/:child_id` or `/file/:id`",
+                    to
+                ),
+            ),
+        }
+    }
+
+    /// the relations must reference the existing data
+    fn relations(&mut self, path: &str, relations: &Value, names: &[String]) {
+        let relations = match self.object(path, relations) {
+            Some(relations) => relations,
+            None => return,
+        };
+
+        for (name, keys) in relations {
+            let path = child(path, name);
+            if !names.contains(name) {
+                self.error(&path, format!("not found data by name '{}'", name));
+            }
+
+            let keys = match self.object(&path, keys) {
+                Some(keys) => keys,
+                None => continue,
+            };
+            for (key, parent) in keys {
+                let path = child(&path, key);
+                match parent.as_str() {
+                    Some(parent) if names.iter().any(|n| n == parent) => {}
+                    Some(parent) => {
+                        self.error(&path, format!("not found data by name '{}'", parent))
+                    }
+                    None => self.error(&path, "must be string type"),
+                }
+            }
+        }
+    }
+
+    /// the primary keys must be the field name or the list of field names
+    fn keys(&mut self, path: &str, keys: &Value, names: &[String]) {
+        let keys = match self.object(path, keys) {
+            Some(keys) => keys,
+            None => return,
+        };
+
+        for (name, key) in keys {
+            let path = child(path, name);
+            if !names.contains(name) {
+                self.error(&path, format!("not found data by name '{}'", name));
+            }
+
+            let valid = match key {
+                Value::String(_) => true,
+                Value::Array(fields) => !fields.is_empty() && fields.iter().all(Value::is_string),
+                _ => false,
+            };
+            if !valid {
+                self.error(&path, "must be string or array of strings");
+            }
+        }
+    }
+
+    fn wrapping(&mut self, path: &str, wrapping: &Value) {
+        let wrapping = match self.object(path, wrapping) {
+            Some(wrapping) => wrapping,
+            None => return,
+        };
+
+        for (key, value) in wrapping {
+            let path = child(path, key);
+            if self.object(&path, value).is_none() {
+                continue;
+            }
+
+            if key == WRAP_KEY_OK && !contains_placeholder(value, WRAP_DATA) {
+                self.error(&path, format!("missing the '{}' placeholder", WRAP_DATA));
+            }
+
+            if key == WRAP_PAGE && !contains_placeholder(value, WRAP_PAGE_ITEMS) {
+                self.error(
+                    &path,
+                    format!("missing the '{}' placeholder", WRAP_PAGE_ITEMS),
+                );
+            }
+        }
+    }
+
+    /// check the data template recursively
+    fn template(&mut self, path: &str, key: &str, value: &Value) {
+        if let Err(err) = template::check_rule(key) {
+            self.error(path, err);
+        }
+
+        match value {
+            Value::String(s) => {
+                if let Err(err) = template::check_string(s) {
+                    self.error(path, err);
+                }
+            }
+            Value::Array(arr) => {
+                if key.contains('|') && arr.is_empty() {
+                    self.error(path, "the array with rule must not be empty");
+                }
+                for (i, item) in arr.iter().enumerate() {
+                    self.template(&format!("{}[{}]", path, i), &i.to_string(), item);
+                }
+            }
+            Value::Object(obj) => {
+                for (key, value) in obj {
+                    self.template(&child(path, key), key, value);
+                }
+            }
+            _ => {}
+        }
+    }
+}
@@ -1,153 +1,649 @@
-use crate::io::BufReader;
-use crate::models::DataConfig;
-use crate::models::NamedQuery;
-use crate::template::gen_data;
+use crate::aggregate;
+use crate::loader;
+use crate::models::{encode_cursor, NamedQuery, PageHeaders, PageInfo};
+use crate::models::{singular, DataConfig};
+use crate::persist::Persister;
+use crate::template::{self, gen_data_with_seed, parse_name, parse_step};
 use crate::util;
-use once_cell::sync::OnceCell;
-use serde_json::Value;
+use once_cell::sync::Lazy;
+use serde_json::{json, Value};
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::RwLock;
-use tracing::error;
+
+/// the error of creating or changing the record
+#[derive(Debug)]
+pub enum CreateError {
+    /// the record with the same id already exists
+    Conflict(String),
+    Invalid(String),
+}
+
+impl std::fmt::Display for CreateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CreateError::Conflict(err) | CreateError::Invalid(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+/// the patch document of the PATCH request
+pub enum Patch {
+    /// RFC 7386 JSON merge patch
+    Merge(Value),
+    /// RFC 6902 JSON patch operations
+    Json(Value),
+}
+
+/// the loaded config which is not applied to the database yet
+pub struct Staged {
+    pub config: Arc<DataConfig>,
+    collections: HashMap<String, Value>,
+    files: HashMap<String, String>,
+    json: Value,
+
+    /// all of the loaded files and directories
+    pub paths: Vec<PathBuf>,
+}
 
 #[derive(Clone)]
 pub struct Database {
     collections: Arc<RwLock<HashMap<String, Value>>>,
-    files: HashMap<String, String>,
-    config: Arc<DataConfig>,
+    files: Arc<RwLock<HashMap<String, String>>>,
+    config: Arc<RwLock<Arc<DataConfig>>>,
+    persister: Option<Persister>,
+    seed: Option<u64>,
+
+    // the loaded config json, used to reset and export the data
+    source: Arc<RwLock<Value>>,
+    snapshots: Arc<RwLock<HashMap<String, HashMap<String, Value>>>>,
 }
 
 // global data config
-pub static DATA_CONFIG: OnceCell<Arc<DataConfig>> = OnceCell::new();
+static DATA_CONFIG: Lazy<RwLock<Arc<DataConfig>>> = Lazy::new(Default::default);
 
 pub fn get_config() -> Arc<DataConfig> {
-    DATA_CONFIG.get().unwrap().clone()
+    DATA_CONFIG.read().unwrap().clone()
+}
+
+/// generate the collections from the data part of the config
+/// only generate the collection with the given name if it is some
+fn generate(
+    data: &Value,
+    data_name: Option<&str>,
+    seed: Option<u64>,
+) -> Result<HashMap<String, Value>, String> {
+    let mut collections = HashMap::new();
+    let data_list = data.as_object().ok_or("data must be object type")?;
+    for (key, value) in data_list {
+        if let Some(data_name) = data_name {
+            if parse_name(key) != data_name {
+                continue;
+            }
+        }
+
+        let (name, data) =
+            gen_data_with_seed(key, value, seed).map_err(|err| format!("data.{}: {}", key, err))?;
+        collections.insert(name, data);
+    }
+
+    Ok(collections)
+}
+
+/// generate the collections from the templates and put the literal `records` over them,
+/// only the collection with the given name if it is some
+fn collections(
+    json: &Value,
+    data_name: Option<&str>,
+    seed: Option<u64>,
+) -> Result<HashMap<String, Value>, String> {
+    let mut collections = match json.get("data") {
+        Some(data) => generate(data, data_name, seed)?,
+        None => HashMap::new(),
+    };
+
+    match json.get("records") {
+        Some(Value::Object(records)) => {
+            let records = records
+                .iter()
+                .filter(|(name, _)| data_name.is_none_or(|data_name| data_name == *name))
+                .map(|(name, value)| (name.clone(), value.clone()));
+            collections.extend(records);
+        }
+        Some(Value::Null) | None => {}
+        Some(_) => return Err("records must be object type".to_string()),
+    }
+
+    Ok(collections)
+}
+
+/// the relation to join into the record
+enum Join {
+    /// embed the children whose foreign key references the record
+    Embed { name: String, key: String },
+    /// replace the foreign key with the parent record
+    Expand {
+        name: String,
+        key: String,
+        parent: String,
+    },
+}
+
+/// resolve the `_embed` and `_expand` relations of the collection
+fn joins(
+    collections: &HashMap<String, Value>,
+    config: &DataConfig,
+    data_name: &str,
+    q: &NamedQuery,
+) -> Result<Vec<Join>, String> {
+    let mut joins = Vec::new();
+    for name in &q.embed {
+        if !collections.contains_key(name) {
+            return Err(format!("not found data by name '{}'", name));
+        }
+
+        let key = config.foreign_key(name, data_name);
+        joins.push(Join::Embed {
+            name: name.clone(),
+            key,
+        });
+    }
+
+    for name in &q.expand {
+        let (key, parent) = match config.expand_key(data_name, name) {
+            Some(v) => v,
+            None => match collections.keys().find(|c| &singular(c) == name) {
+                Some(parent) => (format!("{}Id", name), parent.clone()),
+                None => {
+                    return Err(format!(
+                        "not found the relation '{}' of '{}'",
+                        name, data_name
+                    ))
+                }
+            },
+        };
+        joins.push(Join::Expand {
+            name: name.clone(),
+            key,
+            parent,
+        });
+    }
+
+    Ok(joins)
+}
+
+/// the foreign key and the parent id of the nested route
+type Scope = (String, Value);
+
+/// resolve the nested route `/api/:data/:id/:child`, returns the collection name
+/// and the scope of the foreign key with the parent id when the child is given
+fn scope<'a>(
+    collections: &HashMap<String, Value>,
+    config: &DataConfig,
+    path_map: &'a HashMap<String, String>,
+) -> Result<(&'a str, Option<Scope>), String> {
+    let data_name = path_map.get("data").unwrap();
+    let child = match path_map.get("child") {
+        Some(child) => child,
+        None => return Ok((data_name, None)),
+    };
+
+    let id = path_map.get("id").unwrap();
+    let parent = collections
+        .get(data_name)
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .find(|item| config.is_key(data_name, item, id))
+        .ok_or_else(|| format!("not found data by id {}", id))?;
+
+    if !collections.contains_key(child) {
+        return Err(format!("not found data by name '{}'", child));
+    }
+
+    let key = config.foreign_key(child, data_name);
+    let parent_id = config.key_value(data_name, parent).unwrap_or(Value::Null);
+    Ok((child, Some((key, parent_id))))
+}
+
+/// check the item is in the scope of the parent
+fn in_scope(item: &Value, scope: &Option<Scope>) -> bool {
+    match scope {
+        Some((key, id)) => item.get(key).map(util::key_string) == Some(util::key_string(id)),
+        None => true,
+    }
+}
+
+/// check the key of the changed record is not used by the other records
+fn check_key(
+    config: &DataConfig,
+    data_name: &str,
+    list: &[Value],
+    id: &str,
+    value: &Value,
+) -> Result<(), CreateError> {
+    let key_of = |item: &Value| {
+        config
+            .key_value(data_name, item)
+            .map(|v| util::key_string(&v))
+    };
+    let key = match key_of(value) {
+        Some(key) => key,
+        None => return Ok(()),
+    };
+
+    let is_other = |item: &Value| !config.is_key(data_name, item, id);
+    if list
+        .iter()
+        .any(|item| is_other(item) && key_of(item).as_ref() == Some(&key))
+    {
+        return Err(CreateError::Conflict(format!(
+            "the id {} already exists",
+            key
+        )));
+    }
+
+    Ok(())
 }
 
-impl<'a> Database {
+/// join the relations into the record
+fn join(
+    collections: &HashMap<String, Value>,
+    config: &DataConfig,
+    data_name: &str,
+    joins: &[Join],
+    mut item: Value,
+) -> Value {
+    let id = config
+        .key_value(data_name, &item)
+        .map(|v| util::key_string(&v));
+    let records = |name: &str| {
+        collections
+            .get(name)
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+    };
+
+    if let Value::Object(obj) = &mut item {
+        for join in joins {
+            match join {
+                Join::Embed { name, key } => {
+                    let children = records(name)
+                        .filter(|child| id.is_some() && child.get(key).map(util::key_string) == id)
+                        .cloned()
+                        .collect();
+                    obj.insert(name.clone(), Value::Array(children));
+                }
+                Join::Expand { name, key, parent } => {
+                    let id = obj.remove(key).map(|v| util::key_string(&v));
+                    let record = records(parent)
+                        .find(|p| id.as_ref().is_some_and(|id| config.is_key(parent, p, id)))
+                        .cloned()
+                        .unwrap_or(Value::Null);
+                    obj.insert(name.clone(), record);
+                }
+            }
+        }
+    }
+
+    item
+}
+
+impl Database {
     pub fn new() -> Self {
         Database {
-            config: Arc::new(DataConfig::default()),
+            config: Arc::new(RwLock::new(Arc::new(DataConfig::default()))),
             collections: Arc::new(RwLock::new(HashMap::new())),
-            files: HashMap::new(),
+            files: Arc::new(RwLock::new(HashMap::new())),
+            persister: None,
+            seed: None,
+            source: Arc::new(RwLock::new(Value::Null)),
+            snapshots: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
-    /// init db data
-    pub fn init(&mut self, path: &str) {
-        let file = std::fs::File::open(path);
-        if let Ok(f) = file {
-            let reader = BufReader::new(f);
-            let result: std::result::Result<Value, _> = serde_json::from_reader(reader);
-
-            if let Ok(json) = result {
-                // store the data
-                let mut collections = self.collections.write().unwrap();
-                let config = json.get("config").unwrap();
-                let data_list = json.get("data").unwrap().as_object().unwrap();
-                for (key, value) in data_list {
-                    let (name, data) = gen_data(key, value);
-                    collections.insert(name, data);
-                }
+    /// save the changed collections by the persister
+    pub fn with_persister(mut self, persister: Persister) -> Self {
+        self.persister = Some(persister);
+        self
+    }
 
-                if let Some(file) = json.get("file") {
-                    if let Some(file_list) = file.as_object() {
-                        for (key, value) in file_list {
-                            if let Some(path) = value.as_str() {
-                                self.files.insert(key.to_string(), path.to_string());
-                            } else {
-                                error!("file.{} must be string type", key);
-                            }
-                        }
+    /// generate the data with the seed, it overrides the seed in config
+    pub fn with_seed(mut self, seed: Option<u64>) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// replace the generated collections with the persisted ones
+    /// return false if there is nothing persisted
+    pub fn restore_persisted(&self) -> Result<bool, String> {
+        if let Some(persister) = &self.persister {
+            if let Some(saved) = persister.load()? {
+                // the saved data is not changed, so no need to save it again
+                self.collections.write().unwrap().extend(saved);
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// clone the current collections
+    pub fn snapshot(&self) -> HashMap<String, Value> {
+        self.collections.read().unwrap().clone()
+    }
+
+    /// replace the collections with the given ones,
+    /// the collections not in the given map are kept
+    pub fn restore(&self, collections: HashMap<String, Value>) {
+        self.collections.write().unwrap().extend(collections);
+        self.changed();
+    }
+
+    /// notify the persister that the collections are changed
+    fn changed(&self) {
+        if let Some(persister) = &self.persister {
+            persister.changed();
+        }
+    }
+
+    /// init db data from the config file or directory
+    /// return all of the loaded file paths
+    pub fn init(&self, path: &str) -> Result<Vec<PathBuf>, String> {
+        let staged = self.stage(path)?;
+        let paths = staged.paths.clone();
+        self.apply(staged);
+
+        Ok(paths)
+    }
+
+    /// load the config file without changing the current data
+    pub fn stage(&self, path: &str) -> Result<Staged, String> {
+        let loaded = loader::load(path)?;
+        let mut staged = self.prepare(&loaded.json)?;
+        staged.paths = loaded.paths;
+
+        Ok(staged)
+    }
+
+    /// load the data, files and config from the config json
+    /// the current data is kept when there is any error in the json
+    pub fn load(&self, json: &Value) -> Result<(), String> {
+        let staged = self.prepare(json)?;
+        self.apply(staged);
+
+        Ok(())
+    }
+
+    fn prepare(&self, json: &Value) -> Result<Staged, String> {
+        let config = DataConfig::new(json.get("config").unwrap_or(&Value::Null))?;
+
+        let collections = collections(json, None, self.seed.or(config.seed))?;
+
+        let mut files = HashMap::new();
+        if let Some(file) = json.get("file") {
+            if let Some(file_list) = file.as_object() {
+                for (key, value) in file_list {
+                    if let Some(path) = value.as_str() {
+                        files.insert(key.to_string(), path.to_string());
+                    } else {
+                        return Err(format!("file.{} must be string type", key));
                     }
                 }
+            }
+        }
+
+        Ok(Staged {
+            config: Arc::new(config),
+            collections,
+            files,
+            json: json.clone(),
+            paths: Vec::new(),
+        })
+    }
+
+    /// swap all of the data with the staged one at once
+    pub fn apply(&self, staged: Staged) {
+        *self.collections.write().unwrap() = staged.collections;
+        *self.files.write().unwrap() = staged.files;
+        *self.config.write().unwrap() = staged.config.clone();
+        *DATA_CONFIG.write().unwrap() = staged.config;
+        *self.source.write().unwrap() = staged.json;
+    }
 
-                self.config = Arc::new(DataConfig::new(&config));
-                DATA_CONFIG.set(self.config.clone()).unwrap();
+    /// regenerate all of the collections or the given one from the templates and the records
+    pub fn reset(&self, data_name: Option<&str>) -> Result<Value, String> {
+        let source = self.source.read().unwrap().clone();
+        if source["data"].is_null() && source["records"].is_null() {
+            return Ok(Value::Bool(true));
+        }
+
+        let seed = self.seed.or(self.get_config().seed);
+        let collections = collections(&source, data_name, seed)?;
+        if let Some(data_name) = data_name {
+            if collections.is_empty() {
+                return Err(format!("not found data by name '{}'", data_name));
             }
         }
+        self.restore(collections);
+
+        Ok(Value::Bool(true))
     }
 
-    pub fn get_config(&self) -> &DataConfig {
-        &self.config
+    /// save the current collections as the named snapshot
+    pub fn take_snapshot(&self, name: &str) -> Result<Value, String> {
+        let collections = self.snapshot();
+        self.snapshots
+            .write()
+            .unwrap()
+            .insert(name.to_string(), collections);
+
+        Ok(Value::Bool(true))
     }
 
+    /// restore the collections from the named snapshot
+    pub fn restore_snapshot(&self, name: &str) -> Result<Value, String> {
+        let collections = match self.snapshots.read().unwrap().get(name) {
+            Some(collections) => collections.clone(),
+            None => return Err(format!("not found snapshot by name '{}'", name)),
+        };
+        self.restore(collections);
+
+        Ok(Value::Bool(true))
+    }
+
+    pub fn delete_snapshot(&self, name: &str) -> Result<Value, String> {
+        match self.snapshots.write().unwrap().remove(name) {
+            Some(_) => Ok(Value::Bool(true)),
+            None => Err(format!("not found snapshot by name '{}'", name)),
+        }
+    }
+
+    /// list the snapshot names
+    pub fn snapshot_names(&self) -> Result<Value, String> {
+        let mut names: Vec<String> = self.snapshots.read().unwrap().keys().cloned().collect();
+        names.sort();
+
+        Ok(serde_json::json!(names))
+    }
+
+    /// export the current collections as a config file
+    pub fn export(&self) -> Value {
+        let source = self.source.read().unwrap();
+        let mut json = serde_json::Map::new();
+        if let Some(config) = source.get("config") {
+            json.insert("config".to_string(), config.clone());
+        }
+
+        // the templates are kept for reset, the current data is exported as the literal records
+        if let Some(data) = source.get("data") {
+            json.insert("data".to_string(), data.clone());
+        }
+        let records: serde_json::Map<String, Value> = self.snapshot().into_iter().collect();
+        json.insert("records".to_string(), Value::Object(records));
+
+        if let Some(file) = source.get("file") {
+            json.insert("file".to_string(), file.clone());
+        }
+
+        Value::Object(json)
+    }
+
+    pub fn get_config(&self) -> Arc<DataConfig> {
+        self.config.read().unwrap().clone()
+    }
+
+    /// query data and the page info which is returned when the page headers are enabled
     pub fn query_data(
         &self,
         path_map: &HashMap<String, String>,
-        query: &HashMap<String, String>,
-    ) -> Result<Value, String> {
+        query: &[(String, String)],
+    ) -> Result<(Value, Option<PageInfo>), String> {
         tracing::debug!("query_data: path_map={:?}, query={:?}", path_map, query);
-        let data = self.collections.read().unwrap();
+        let mut q = NamedQuery::from(query)?;
+        let (value, info) = self.query_named(path_map, &mut q)?;
 
-        let data_name = path_map.get("data").unwrap();
-        let q = NamedQuery::from(&query);
-
-        let json_option = data.get(data_name);
-        if json_option == None {
-            return Err(format!("not found data by name '{}'", data_name));
+        // select the result by _select
+        match &q.select {
+            Some(expr) => Ok((util::select(&value, expr)?, info)),
+            None => Ok((value, info)),
         }
-        let json = json_option.unwrap();
+    }
+
+    fn query_named(
+        &self,
+        path_map: &HashMap<String, String>,
+        q: &mut NamedQuery,
+    ) -> Result<(Value, Option<PageInfo>), String> {
+        let data = self.collections.read().unwrap();
+        let config = self.get_config();
+        let (data_name, scope) = scope(&data, &config, path_map)?;
+
+        let json = match data.get(data_name) {
+            Some(json) => json,
+            None => return Err(format!("not found data by name '{}'", data_name)),
+        };
 
         if !json.is_array() {
-            return Ok(json.clone());
+            return Ok((q.project(json.clone()), None));
         }
 
-        // query data by input parameters
         let arr = json.as_array().unwrap();
-        let mut list: Vec<Value> = Vec::new();
+        q.filter_by(arr)?;
+        let q = &*q;
 
-        for item in arr {
-            if q.is_match(item) {
-                list.push(item.clone())
-            }
-        }
+        let joins = joins(&data, &config, data_name, q)?;
+        let output = |item: Value| q.project(join(&data, &config, data_name, &joins, item));
 
-        if let Some(sort) = q.sort {
-            let mut is_desc = false;
+        // query data by input parameters
+        let matched: Vec<&Value> = arr
+            .iter()
+            .filter(|item| in_scope(item, &scope) && q.is_match(item))
+            .collect();
 
-            if let Some(order) = q.order {
-                if order == "desc" {
-                    is_desc = true;
-                }
-            }
+        // the aggregation respects the filters
+        if q.is_aggregate() {
+            return Ok((aggregate::aggregate(&matched, q), None));
+        }
+        let mut list: Vec<Value> = matched.into_iter().cloned().collect();
 
-            list.sort_by(|a, b| {
-                let a_value = &a[&sort];
-                let b_value = &b[&sort];
+        // the sort is stable, so the same keys keep the original order
+        if !q.sort.is_empty() {
+            list.sort_by(|a, b| q.cmp(a, b));
+        }
 
-                if is_desc {
-                    return util::cmp(b_value, a_value);
-                }
+        let total = list.len();
 
-                util::cmp(a_value, b_value)
-            })
+        // cursor pagination
+        if let Some(offset) = q.cursor {
+            let size = q.limit.or(q.size).unwrap_or(10);
+            let items: Vec<Value> = list
+                .into_iter()
+                .skip(offset)
+                .take(size)
+                .map(output)
+                .collect();
+            let next = offset.saturating_add(size);
+            let next_cursor = (next < total).then(|| encode_cursor(next));
+
+            let page_data = util::wrap_page(
+                &config.wrapping,
+                items.iter().collect(),
+                total,
+                None,
+                size,
+                next_cursor,
+                None,
+            );
+            return Ok((page_data, None));
         }
 
         // pagination
-        if q.page.is_some() {
+        if let Some(page) = q.page {
             // default page size to 10
-            let size = q.size.unwrap_or(10) as usize;
-            let page = q.page.unwrap() as usize;
+            let size = q.size.unwrap_or(10);
+            let items: Vec<Value> = list
+                .into_iter()
+                .skip((page - 1).saturating_mul(size))
+                .take(size)
+                .map(output)
+                .collect();
 
-            let total = list.len();
-            let items: Vec<&Value> = list.iter().skip((page - 1) * size).take(size).collect();
+            let page_data = match config.page_headers {
+                PageHeaders::Headers => json!(items),
+                _ => {
+                    let items = items.iter().collect();
+                    util::wrap_page(&config.wrapping, items, total, Some(page), size, None, None)
+                }
+            };
+            let info = (config.page_headers != PageHeaders::Body).then_some(PageInfo {
+                page,
+                size,
+                total,
+            });
+            return Ok((page_data, info));
+        }
 
-            let config = self.get_config();
-            let page_data = util::wrap_page(&config.wrapping, items, total, page, size, None);
-            return Ok(page_data);
+        // slice by _start, _end or _limit, the _end takes precedence over _limit
+        if q.start.is_some() || q.end.is_some() || q.limit.is_some() {
+            let start = q.start.unwrap_or(0);
+            let end = match (q.end, q.limit) {
+                (Some(end), _) => end,
+                (None, Some(limit)) => start.saturating_add(limit),
+                _ => total,
+            };
+            list = list
+                .into_iter()
+                .skip(start)
+                .take(end.saturating_sub(start))
+                .collect();
         }
 
-        Ok(Value::Array(list))
+        let list = list.into_iter().map(output).collect();
+        Ok((Value::Array(list), None))
     }
 
-    pub fn get_data(&self, path_map: &HashMap<String, String>) -> Result<Value, String> {
-        tracing::debug!("get_data: path_map={:?}", path_map);
+    pub fn get_data(
+        &self,
+        path_map: &HashMap<String, String>,
+        query: &[(String, String)],
+    ) -> Result<Value, String> {
+        tracing::debug!("get_data: path_map={:?}, query={:?}", path_map, query);
         let collections = self.collections.read().unwrap();
-
-        let data_name = path_map.get("data").unwrap();
-        let id = path_map.get("id").unwrap();
-        let res_data = &collections[data_name];
+        let q = NamedQuery::from(query)?;
+        let config = self.get_config();
+
+        // the child id of the nested route takes precedence
+        let (data_name, scope) = scope(&collections, &config, path_map)?;
+        let id = path_map
+            .get("child_id")
+            .or_else(|| path_map.get("id"))
+            .unwrap();
+        let res_data = collections
+            .get(data_name)
+            .ok_or_else(|| format!("not found data by name '{}'", data_name))?;
 
         if !res_data.is_null() && res_data.is_array() {
             // query data by input parameters
@@ -155,12 +651,23 @@ impl<'a> Database {
                 .as_array()
                 .unwrap()
                 .iter()
-                .filter(|item| &item["id"].to_string() == id)
-                .map(|item| item.clone())
+                .filter(|item| config.is_key(data_name, item, id) && in_scope(item, &scope))
+                .cloned()
                 .collect();
 
             if arr.len() == 1 {
-                Ok(arr[0].clone())
+                let joins = joins(&collections, &config, data_name, &q)?;
+                let value = q.project(join(
+                    &collections,
+                    &config,
+                    data_name,
+                    &joins,
+                    arr[0].clone(),
+                ));
+                match &q.select {
+                    Some(expr) => util::select(&value, expr),
+                    None => Ok(value),
+                }
             } else if arr.len() > 1 {
                 Err(format!("found multipe records by id {}", id))
             } else {
@@ -171,52 +678,189 @@ impl<'a> Database {
         }
     }
 
+    /// create the record, the id is assigned if it is missing
     pub fn create_data(
         &mut self,
         path_map: &HashMap<String, String>,
         value: Value,
-    ) -> Result<Value, String> {
+    ) -> Result<Value, CreateError> {
         tracing::debug!("create_data path_map={:?}, data={:?}", path_map, value);
         let collections = &mut self.collections.write().unwrap();
 
-        let data_name = path_map.get("data").unwrap();
-        let json = collections.get_mut(data_name).unwrap();
+        let mut value = value;
+        let obj = value
+            .as_object_mut()
+            .ok_or_else(|| CreateError::Invalid("the record must be object type".to_string()))?;
+
+        // set the foreign key to the parent id for the nested route
+        let config = self.get_config();
+        let (data_name, scope) =
+            scope(collections, &config, path_map).map_err(CreateError::Invalid)?;
+        if let Some((key, id)) = scope {
+            obj.insert(key, id);
+        }
+
+        let list = collections
+            .get_mut(data_name)
+            .and_then(|json| json.as_array_mut())
+            .ok_or_else(|| {
+                CreateError::Invalid(format!("not found data by name '{}'", data_name))
+            })?;
+
+        // only the single key can be assigned
+        let fields = config.primary_key(data_name);
+        if let [field] = fields.as_slice() {
+            if !obj.contains_key(field) {
+                let id = self.next_id(data_name, field, list);
+                obj.insert(field.clone(), id);
+            }
+        }
+
+        let key = config
+            .key_value(data_name, &value)
+            .map(|v| util::key_string(&v))
+            .ok_or_else(|| {
+                CreateError::Invalid(format!("missing the key '{}'", fields.join(",")))
+            })?;
+        let key_of = |item: &Value| {
+            config
+                .key_value(data_name, item)
+                .map(|v| util::key_string(&v))
+        };
+        if list.iter().any(|item| key_of(item).as_ref() == Some(&key)) {
+            return Err(CreateError::Conflict(format!(
+                "the id {} already exists",
+                key
+            )));
+        }
 
-        let list = json.as_array_mut().unwrap();
         list.push(value.clone());
+        self.changed();
 
         Ok(value)
     }
 
+    /// the next id of the collection, continue the `id|+1` sequence or generate the `@uuid`
+    /// from the template, otherwise increase the max number id or generate uuid
+    fn next_id(&self, data_name: &str, field: &str, list: &[Value]) -> Value {
+        let source = self.source.read().unwrap();
+        let template = source["data"]
+            .as_object()
+            .and_then(|data| data.iter().find(|(key, _)| parse_name(key) == data_name))
+            .map(|(_, value)| match value {
+                Value::Array(arr) => arr.first().cloned().unwrap_or(Value::Null),
+                v => v.clone(),
+            })
+            .unwrap_or(Value::Null);
+        let id_template = template
+            .as_object()
+            .and_then(|obj| obj.iter().find(|(key, _)| parse_name(key) == field));
+
+        let max_id = list.iter().filter_map(|item| item[field].as_i64()).max();
+        let is_uuid = match id_template {
+            Some((key, value)) => {
+                // the generator makes the ids as `index + step`
+                if let Some(step) = parse_step(key) {
+                    return json!(max_id.map_or(step as i64, |id| id + 1));
+                }
+                value.as_str().is_some_and(|v| v.contains("@uuid"))
+            }
+            None => list.iter().any(|item| item[field].is_string()),
+        };
+
+        if is_uuid {
+            return json!(template::uuid());
+        }
+
+        json!(max_id.unwrap_or(0) + 1)
+    }
+
     pub fn update_data(
         &mut self,
         path_map: &HashMap<std::string::String, std::string::String>,
         value: Value,
-    ) -> Result<Value, String> {
+    ) -> Result<Value, CreateError> {
         tracing::debug!("update_data path_map={:?}, data={:?}", path_map, value);
         let collections = &mut self.collections.write().unwrap();
 
         let data_name = path_map.get("data").unwrap();
         let id = path_map.get("id").unwrap();
+        let config = self.get_config();
 
-        let json = collections.get_mut(data_name).unwrap();
-
-        let list = json.as_array_mut().unwrap();
-        let found_list: Vec<_> = list
-            .into_iter()
-            .filter(|item| &item["id"].to_string() == id)
-            .collect();
-        let len = found_list.len();
+        let json = collections.get_mut(data_name).ok_or_else(|| {
+            CreateError::Invalid(format!("not found data by name '{}'", data_name))
+        })?;
+        let list = json
+            .as_array_mut()
+            .ok_or_else(|| CreateError::Invalid(format!("not found item by id {id}")))?;
 
         // check if the item exists
-        if len == 0 {
-            return Err(format!("not found item by id {id}"));
+        if !list.iter().any(|item| config.is_key(data_name, item, id)) {
+            return Err(CreateError::Invalid(format!("not found item by id {id}")));
         }
 
+        // the new key must not be used by the other records
+        check_key(&config, data_name, list, id, &value)?;
+        let found_list: Vec<_> = list
+            .iter_mut()
+            .filter(|item| config.is_key(data_name, item, id))
+            .collect();
+
         // modify the item data
         found_list.into_iter().for_each(|item: &mut Value| {
             *item = value.clone();
         });
+        self.changed();
+
+        Ok(value)
+    }
+
+    /// patch the item and return the updated item
+    pub fn patch_data(
+        &mut self,
+        path_map: &HashMap<String, String>,
+        patch: Patch,
+    ) -> Result<Value, CreateError> {
+        tracing::debug!("patch_data path_map={:?}", path_map);
+        let collections = &mut self.collections.write().unwrap();
+
+        let data_name = path_map.get("data").unwrap();
+        let id = path_map.get("id").unwrap();
+        let config = self.get_config();
+
+        let json = collections.get_mut(data_name).ok_or_else(|| {
+            CreateError::Invalid(format!("not found data by name '{}'", data_name))
+        })?;
+        let list = json
+            .as_array_mut()
+            .ok_or_else(|| CreateError::Invalid(format!("not found item by id {id}")))?;
+        let index = list
+            .iter()
+            .position(|item| config.is_key(data_name, item, id))
+            .ok_or_else(|| CreateError::Invalid(format!("not found item by id {id}")))?;
+
+        // patch a copy, so the item keeps unchanged on error
+        let mut value = list[index].clone();
+        match patch {
+            // the other merge patches replace the whole record
+            Patch::Merge(patch) if !patch.is_object() => {
+                return Err(CreateError::Invalid(
+                    "the merge patch must be object type".to_string(),
+                ))
+            }
+            Patch::Merge(patch) => json_patch::merge(&mut value, &patch),
+            Patch::Json(patch) => {
+                let patch: json_patch::Patch = serde_json::from_value(patch)
+                    .map_err(|err| CreateError::Invalid(format!("invalid json patch: {}", err)))?;
+                json_patch::patch(&mut value, &patch)
+                    .map_err(|err| CreateError::Invalid(format!("failed to patch: {}", err)))?;
+            }
+        }
+
+        // the changed key must not be used by the other records
+        check_key(&config, data_name, list, id, &value)?;
+        list[index] = value.clone();
+        self.changed();
 
         Ok(value)
     }
@@ -230,25 +874,21 @@ impl<'a> Database {
 
         let data_name = path_map.get("data").unwrap();
         let id = path_map.get("id").unwrap();
-        let json = collections.get_mut(data_name).unwrap();
-
-        let list = json.as_array_mut().unwrap();
-        let found_index: Vec<_> = list
-            .into_iter()
-            .enumerate()
-            .filter(|(_, item)| &item["id"].to_string() == id)
-            .map(|(index, _)| index)
-            .collect();
-        let len = found_index.len();
+        let config = self.get_config();
+        let json = collections
+            .get_mut(data_name)
+            .ok_or_else(|| format!("not found data by name '{}'", data_name))?;
+        let list = json
+            .as_array_mut()
+            .ok_or_else(|| format!("not found item by id {id}"))?;
+        let len = list.len();
+        list.retain(|item| !config.is_key(data_name, item, id));
 
         // check if the item exists
-        if len == 0 {
+        if list.len() == len {
             return Err(format!("not found item by id {id}"));
         }
-
-        for index in found_index {
-            list.remove(index);
-        }
+        self.changed();
 
         Ok(Value::Bool(true))
     }
@@ -258,7 +898,7 @@ impl<'a> Database {
         path_map: &HashMap<std::string::String, std::string::String>,
     ) -> Result<String, String> {
         let file_id = path_map.get("id").unwrap();
-        match self.files.get(file_id) {
+        match self.files.read().unwrap().get(file_id) {
             Some(v) => Ok(v.clone()),
             None => Err(format!("not found item by id {}", file_id)),
         }
@@ -0,0 +1,193 @@
+use crate::{template, Database};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+/// the format to export the generated data
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Json,
+    Ndjson,
+    Csv,
+    Sql,
+}
+
+impl ExportFormat {
+    /// the file extension of the format
+    pub fn ext(&self) -> &str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Ndjson => "ndjson",
+            ExportFormat::Csv => "csv",
+            ExportFormat::Sql => "sql",
+        }
+    }
+
+    /// format the collection data
+    pub fn format(&self, name: &str, data: &Value) -> String {
+        match self {
+            ExportFormat::Json => serde_json::to_string_pretty(data).unwrap() + "\n",
+            ExportFormat::Ndjson => rows(data)
+                .iter()
+                .map(|row| row.to_string() + "\n")
+                .collect(),
+            ExportFormat::Csv => csv(data),
+            ExportFormat::Sql => sql(name, data),
+        }
+    }
+}
+
+/// generate the collections from the data part of the config
+///
+/// `names` - the collections to generate, generate all if it is empty
+/// `counts` - override the count of the collections, such as `users|10` for `users`
+pub fn generate(
+    json: &Value,
+    names: &[String],
+    counts: &HashMap<String, usize>,
+    seed: Option<u64>,
+) -> Result<Vec<(String, Value)>, String> {
+    let mut json = json.clone();
+    if let Some(data) = json.get_mut("data").and_then(|data| data.as_object_mut()) {
+        let mut new_data = Map::new();
+        for (key, value) in std::mem::take(data) {
+            let name = template::parse_name(&key);
+            if !names.is_empty() && !names.contains(&name) {
+                continue;
+            }
+
+            match counts.get(&name) {
+                Some(count) => new_data.insert(format!("{}|{}", name, count), value),
+                None => new_data.insert(key, value),
+            };
+        }
+        *data = new_data;
+    }
+
+    for name in names.iter().chain(counts.keys()) {
+        let found = json["data"]
+            .as_object()
+            .map(|data| data.keys().any(|key| &template::parse_name(key) == name))
+            .unwrap_or(false);
+        if !found {
+            return Err(format!("not found data by name '{}'", name));
+        }
+    }
+
+    let db = Database::new().with_seed(seed);
+    db.load(&json)?;
+
+    let mut collections: Vec<(String, Value)> = db.snapshot().into_iter().collect();
+    collections.sort_by(|a, b| a.0.cmp(&b.0));
+
+    Ok(collections)
+}
+
+/// the rows of the data, the data which is not array is one row
+fn rows(data: &Value) -> Vec<&Value> {
+    match data {
+        Value::Array(arr) => arr.iter().collect(),
+        _ => vec![data],
+    }
+}
+
+/// the column names of the rows, `value` for the row which is not object
+fn columns(rows: &[&Value]) -> Vec<String> {
+    let mut columns: Vec<String> = Vec::new();
+    for row in rows {
+        match row {
+            Value::Object(obj) => {
+                for key in obj.keys() {
+                    if !columns.contains(key) {
+                        columns.push(key.clone());
+                    }
+                }
+            }
+            _ => {
+                if !columns.iter().any(|c| c == "value") {
+                    columns.push("value".to_string());
+                }
+            }
+        }
+    }
+
+    columns
+}
+
+/// the cell value of the row by column
+fn cell<'a>(row: &'a Value, column: &str) -> &'a Value {
+    match row {
+        Value::Object(obj) => obj.get(column).unwrap_or(&Value::Null),
+        _ if column == "value" => row,
+        _ => &Value::Null,
+    }
+}
+
+fn csv(data: &Value) -> String {
+    let escape = |text: &str| {
+        if text.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", text.replace('"', "\"\""))
+        } else {
+            text.to_string()
+        }
+    };
+
+    let rows = rows(data);
+    let columns = columns(&rows);
+    let mut ret = columns
+        .iter()
+        .map(|c| escape(c))
+        .collect::<Vec<_>>()
+        .join(",")
+        + "\n";
+
+    for row in &rows {
+        let line: Vec<String> = columns
+            .iter()
+            .map(|column| match cell(row, column) {
+                Value::Null => String::new(),
+                Value::String(s) => escape(s),
+                v => escape(&v.to_string()),
+            })
+            .collect();
+        ret.push_str(&line.join(","));
+        ret.push('\n');
+    }
+
+    ret
+}
+
+fn sql(name: &str, data: &Value) -> String {
+    let ident = |text: &str| format!("\"{}\"", text.replace('"', "\"\""));
+    let string = |text: &str| format!("'{}'", text.replace('\'', "''"));
+
+    let rows = rows(data);
+    let columns = columns(&rows);
+    let column_list = columns
+        .iter()
+        .map(|c| ident(c))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let mut ret = String::new();
+    for row in &rows {
+        let values: Vec<String> = columns
+            .iter()
+            .map(|column| match cell(row, column) {
+                Value::Null => "NULL".to_string(),
+                Value::Bool(b) => b.to_string().to_uppercase(),
+                Value::Number(n) => n.to_string(),
+                Value::String(s) => string(s),
+                v => string(&v.to_string()),
+            })
+            .collect();
+
+        ret.push_str(&format!(
+            "INSERT INTO {} ({}) VALUES ({});\n",
+            ident(name),
+            column_list,
+            values.join(", ")
+        ));
+    }
+
+    ret
+}
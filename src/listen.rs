@@ -0,0 +1,144 @@
+use axum::Router;
+use axum_server::tls_rustls::RustlsConfig;
+use std::{
+    fmt,
+    net::{IpAddr, SocketAddr},
+    path::PathBuf,
+};
+
+const UNIX_PREFIX: &str = "unix:";
+
+/// the address the server listens on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Listen {
+    Tcp(SocketAddr),
+    Unix(PathBuf),
+}
+
+impl Listen {
+    /// parse the listen address from `ip`, `ip:port`, `[ipv6]:port` or `unix:/path`
+    /// the ip without port listens on the given default port
+    pub fn parse(value: &str, port: u16) -> Result<Self, String> {
+        if let Some(path) = value.strip_prefix(UNIX_PREFIX) {
+            if !cfg!(unix) {
+                return Err(format!(
+                    "{}: unix socket is not supported on this platform",
+                    value
+                ));
+            }
+
+            if path.is_empty() {
+                return Err(format!("{}: missing unix socket path", value));
+            }
+            return Ok(Listen::Unix(PathBuf::from(path)));
+        }
+
+        if let Ok(addr) = value.parse::<SocketAddr>() {
+            return Ok(Listen::Tcp(addr));
+        }
+
+        // accept the ipv6 address in brackets without port, such as `[::1]`
+        let ip = value.trim_start_matches('[').trim_end_matches(']');
+        match ip.parse::<IpAddr>() {
+            Ok(ip) => Ok(Listen::Tcp(SocketAddr::new(ip, port))),
+            Err(_) => Err(format!("{}: invalid listen address", value)),
+        }
+    }
+
+    /// serve the app on the address until the server fails
+    pub async fn serve(self, app: Router) -> Result<(), String> {
+        match self {
+            Listen::Tcp(addr) => axum::Server::try_bind(&addr)
+                .map_err(|err| format!("{}: {}", addr, err))?
+                .serve(app.into_make_service())
+                .await
+                .map_err(|err| format!("{}: {}", addr, err)),
+
+            #[cfg(unix)]
+            Listen::Unix(path) => {
+                // remove the socket file left by the last run, but never the other files
+                use std::os::unix::fs::FileTypeExt;
+                let metadata = std::fs::symlink_metadata(&path);
+                if metadata.is_ok_and(|m| m.file_type().is_socket()) {
+                    let _ = std::fs::remove_file(&path);
+                }
+                let listener = tokio::net::UnixListener::bind(&path)
+                    .map_err(|err| format!("{}: {}", path.display(), err))?;
+
+                axum::Server::builder(unix::UnixAccept(listener))
+                    .serve(app.into_make_service())
+                    .await
+                    .map_err(|err| format!("{}: {}", path.display(), err))
+            }
+
+            #[cfg(not(unix))]
+            Listen::Unix(path) => Err(format!(
+                "{}: unix socket is not supported on this platform",
+                path.display()
+            )),
+        }
+    }
+
+    /// serve the app over tls on the address until the server fails
+    pub async fn serve_tls(self, app: Router, config: RustlsConfig) -> Result<(), String> {
+        match self {
+            Listen::Tcp(addr) => axum_server::bind_rustls(addr, config)
+                .serve(app.into_make_service())
+                .await
+                .map_err(|err| format!("{}: {}", addr, err)),
+            Listen::Unix(path) => Err(format!(
+                "{}: tls is not supported on the unix socket",
+                path.display()
+            )),
+        }
+    }
+
+    /// the same address with another port
+    pub fn with_port(&self, port: u16) -> Option<Listen> {
+        match self {
+            Listen::Tcp(addr) => Some(Listen::Tcp(SocketAddr::new(addr.ip(), port))),
+            Listen::Unix(_) => None,
+        }
+    }
+
+    pub fn is_tcp(&self) -> bool {
+        matches!(self, Listen::Tcp(_))
+    }
+}
+
+impl fmt::Display for Listen {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Listen::Tcp(addr) => write!(f, "{}", addr),
+            Listen::Unix(path) => write!(f, "{}{}", UNIX_PREFIX, path.display()),
+        }
+    }
+}
+
+#[cfg(unix)]
+mod unix {
+    use hyper::server::accept::Accept;
+    use std::{
+        io,
+        pin::Pin,
+        task::{Context, Poll},
+    };
+    use tokio::net::{UnixListener, UnixStream};
+
+    /// accept the connections from the unix socket for the hyper server
+    pub struct UnixAccept(pub UnixListener);
+
+    impl Accept for UnixAccept {
+        type Conn = UnixStream;
+        type Error = io::Error;
+
+        fn poll_accept(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+        ) -> Poll<Option<Result<Self::Conn, Self::Error>>> {
+            self.0
+                .poll_accept(cx)
+                .map(|ret| Some(ret.map(|(stream, _)| stream)))
+        }
+    }
+}
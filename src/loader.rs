@@ -0,0 +1,245 @@
+use crate::template;
+use serde_json::{Map, Value};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+/// the config file format selected by the file extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Json5,
+    Yaml,
+    Toml,
+}
+
+impl Format {
+    /// get the format from the file extension, default to json
+    pub fn from_path(path: &Path) -> Format {
+        let ext = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+
+        match ext.as_str() {
+            "json5" => Format::Json5,
+            "yaml" | "yml" => Format::Yaml,
+            "toml" => Format::Toml,
+            _ => Format::Json,
+        }
+    }
+
+    /// check if the file extension is one of the supported formats
+    pub fn is_supported(path: &Path) -> bool {
+        let ext = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+
+        matches!(ext.as_str(), "json" | "json5" | "yaml" | "yml" | "toml")
+    }
+
+    /// parse the text to the json value
+    pub fn parse(&self, text: &str) -> Result<Value, String> {
+        match self {
+            Format::Json => serde_json::from_str(text).map_err(|err| err.to_string()),
+            Format::Json5 => json5::from_str(text).map_err(|err| err.to_string()),
+            Format::Yaml => serde_yaml::from_str(text).map_err(|err| err.to_string()),
+            Format::Toml => toml::from_str(text).map_err(|err| err.to_string()),
+        }
+    }
+}
+
+/// read the config file to json by its format
+pub fn read_file(path: &str) -> Result<Value, String> {
+    let text = std::fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+    Format::from_path(Path::new(path))
+        .parse(&text)
+        .map_err(|err| format!("{}: {}", path, err))
+}
+
+/// the key to include other config files
+const INCLUDE_KEY: &str = "include";
+
+/// the config merged from the files
+#[derive(Debug, Default)]
+pub struct Loaded {
+    pub json: Value,
+
+    /// all of the loaded files and directories
+    pub paths: Vec<PathBuf>,
+}
+
+/// load the config from the file or directory,
+/// the files in the directory and the included files are merged into one config
+pub fn load(path: &str) -> Result<Loaded, String> {
+    let mut loader = Loader::default();
+    loader.load(Path::new(path))?;
+
+    Ok(Loaded {
+        json: Value::Object(loader.json),
+        paths: loader.paths,
+    })
+}
+
+#[derive(Default)]
+struct Loader {
+    json: Map<String, Value>,
+    paths: Vec<PathBuf>,
+
+    // the file where the merged key is from, such as `data/users`
+    origins: HashMap<String, PathBuf>,
+}
+
+impl Loader {
+    fn load(&mut self, path: &Path) -> Result<(), String> {
+        let canonical = path
+            .canonicalize()
+            .map_err(|err| format!("{}: {}", path.display(), err))?;
+        if self.paths.contains(&canonical) {
+            // the file is already included
+            return Ok(());
+        }
+        self.paths.push(canonical);
+
+        if path.is_dir() {
+            return self.load_dir(path);
+        }
+
+        let mut json = read_file(&path.to_string_lossy())?;
+        let obj = json.as_object_mut().ok_or(format!(
+            "{}: the config must be object type",
+            path.display()
+        ))?;
+        let include = obj.remove(INCLUDE_KEY);
+        self.merge(path, obj)?;
+
+        if let Some(include) = include {
+            let list = include.as_array().ok_or(format!(
+                "{}: {} must be array type",
+                path.display(),
+                INCLUDE_KEY
+            ))?;
+
+            let base = path.parent().unwrap_or_else(|| Path::new(""));
+            for item in list {
+                let file = item.as_str().ok_or(format!(
+                    "{}: {} must be the list of paths",
+                    path.display(),
+                    INCLUDE_KEY
+                ))?;
+                self.load(&base.join(file))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// load the config files in the directory by the name order
+    fn load_dir(&mut self, path: &Path) -> Result<(), String> {
+        let entries =
+            std::fs::read_dir(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+        let mut files = Vec::new();
+        for entry in entries {
+            let file = entry
+                .map_err(|err| format!("{}: {}", path.display(), err))?
+                .path();
+            if file.is_file() && Format::is_supported(&file) {
+                files.push(file);
+            }
+        }
+        files.sort();
+
+        for file in files {
+            self.load(&file)?;
+        }
+
+        Ok(())
+    }
+
+    fn merge(&mut self, path: &Path, source: &Map<String, Value>) -> Result<(), String> {
+        for (section, value) in source {
+            match (section.as_str(), value) {
+                // the collection names are compared without the rules
+                ("data", Value::Object(data)) => {
+                    for (key, value) in data {
+                        let name = template::parse_name(key);
+                        self.insert(path, "collection", &["data"], &name, key, value)?;
+                    }
+                }
+                ("records", Value::Object(records)) => {
+                    for (name, value) in records {
+                        self.insert(path, "collection", &["records"], name, name, value)?;
+                    }
+                }
+                ("config", Value::Object(config)) => {
+                    for (name, value) in config {
+                        match value {
+                            Value::Object(items) => {
+                                let kind = if name == "routing" { "route" } else { name };
+                                for (key, value) in items {
+                                    self.insert(path, kind, &["config", name], key, key, value)?;
+                                }
+                            }
+                            _ => self.insert(path, "config", &["config"], name, name, value)?,
+                        }
+                    }
+                }
+                ("file", Value::Object(files)) => {
+                    for (key, value) in files {
+                        self.insert(path, "file", &["file"], key, key, value)?;
+                    }
+                }
+                ("data", _) | ("records", _) | ("config", _) | ("file", _) => {
+                    return Err(format!(
+                        "{}: {} must be object type",
+                        path.display(),
+                        section
+                    ))
+                }
+                _ => self.insert(path, "key", &[], section, section, value)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// insert the value to the nested object by the parents,
+    /// return error if the name is already defined by another file
+    fn insert(
+        &mut self,
+        path: &Path,
+        kind: &str,
+        parents: &[&str],
+        name: &str,
+        key: &str,
+        value: &Value,
+    ) -> Result<(), String> {
+        let origin = format!("{}/{}", parents.join("/"), name);
+        if let Some(other) = self.origins.get(&origin) {
+            return Err(format!(
+                "{}: {} '{}' is already defined in {}",
+                path.display(),
+                kind,
+                name,
+                other.display()
+            ));
+        }
+        self.origins.insert(origin, path.to_path_buf());
+
+        let mut target = &mut self.json;
+        for parent in parents {
+            target = target
+                .entry(parent.to_string())
+                .or_insert_with(|| Value::Object(Map::new()))
+                .as_object_mut()
+                .unwrap();
+        }
+        target.insert(key.to_string(), value.clone());
+
+        Ok(())
+    }
+}
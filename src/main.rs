@@ -2,20 +2,34 @@
 
 #![doc = include_str!("../README.md")]
 
+use app::AppRouter;
+use axum::http::header;
 use axum::Extension;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use db::Database;
-use std::{collections::HashMap, io, net::SocketAddr};
+use export::ExportFormat;
+use listen::Listen;
+use persist::Persister;
+use std::collections::HashMap;
 use tower_http::cors::{AllowHeaders, AllowMethods, AllowOrigin, CorsLayer};
 use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, Registry};
 
+mod admin;
+mod aggregate;
 mod app;
+mod check;
 mod db;
+mod export;
 mod extends;
+mod listen;
+mod loader;
 mod models;
+mod persist;
 mod service;
 mod template;
+mod tls;
 mod util;
+mod watch;
 
 #[cfg(test)]
 mod tests;
@@ -23,39 +37,363 @@ mod tests;
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = include_str!("./docs/help.md"))]
 struct Args {
+    #[clap(subcommand)]
+    command: Option<Command>,
+
     #[clap(short, long, value_parser, default_value = "./static/db.json")]
     config: String,
 
     #[clap(short, long, value_parser, default_value_t = 8080)]
-    port: u32,
+    port: u16,
+
+    /// the address to listen, such as `0.0.0.0`, `[::1]:8080` or `unix:/tmp/mock.sock`,
+    /// it can be given multiple times to listen on all of them
+    #[clap(
+        short,
+        long,
+        visible_alias = "host",
+        value_parser,
+        default_value = "127.0.0.1"
+    )]
+    listen: Vec<String>,
+
+    /// the certificate pem file to serve https
+    #[clap(long, value_parser, requires = "tls-key")]
+    tls_cert: Option<String>,
+
+    /// the private key pem file to serve https
+    #[clap(long, value_parser, requires = "tls-cert")]
+    tls_key: Option<String>,
+
+    /// serve https on this port alongside http,
+    /// the tcp listeners serve https instead of http if it is not set
+    #[clap(long, value_parser, requires = "tls-cert")]
+    tls_port: Option<u16>,
+
+    /// generate a self-signed certificate to the tls files if they do not exist
+    #[clap(long, value_parser, requires = "tls-cert")]
+    tls_self_signed: bool,
+
+    /// reload the config file when it changes
+    #[clap(short, long, value_parser)]
+    watch: bool,
+
+    /// the seed to generate the same data on every start, it overrides the `seed` in config
+    #[clap(long, value_parser, global = true)]
+    seed: Option<u64>,
+
+    /// save the changed data to the file and load it on the next start
+    #[clap(long, value_parser)]
+    persist: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// check the config file and report the problems with their json paths
+    Check {
+        /// the config file to check, default to the `--config`
+        #[clap(value_parser)]
+        config: Option<String>,
+    },
+
+    /// generate the data from the config without running the server
+    Generate {
+        /// the config file to generate, default to the `--config`
+        #[clap(value_parser)]
+        config: Option<String>,
+
+        /// the output format
+        #[clap(short, long, value_enum, default_value = "json")]
+        format: ExportFormat,
+
+        /// write each collection to `<out>/<name>.<ext>` instead of stdout
+        #[clap(short, long, value_parser)]
+        out: Option<String>,
+
+        /// the collection to generate, generate all if it is not given
+        #[clap(short = 'n', long = "collection", value_parser)]
+        collections: Vec<String>,
+
+        /// override the collection count by `name=count`
+        #[clap(long = "count", value_parser = parse_count)]
+        counts: Vec<(String, usize)>,
+    },
+}
+
+fn parse_count(value: &str) -> Result<(String, usize), String> {
+    let (name, count) = value
+        .split_once('=')
+        .ok_or_else(|| format!("invalid count '{}', expect `name=count`", value))?;
+    let count = count
+        .parse()
+        .map_err(|err| format!("invalid count '{}': {}", value, err))?;
+
+    Ok((name.to_string(), count))
+}
+
+/// check the config file and exit with non-zero code if there is any problem
+fn run_check(path: &str) -> ! {
+    let json = match loader::load(path) {
+        Ok(loaded) => loaded.json,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    };
+
+    let problems = check::check(&json);
+    if problems.is_empty() {
+        println!("{}: ok", path);
+        std::process::exit(0);
+    }
+
+    for problem in &problems {
+        eprintln!("{}: {}", path, problem);
+    }
+    eprintln!("{}: found {} problem(s)", path, problems.len());
+    std::process::exit(1);
+}
+
+/// generate the data and write it to stdout or files
+fn run_generate(
+    path: &str,
+    seed: Option<u64>,
+    format: ExportFormat,
+    out: Option<&str>,
+    names: &[String],
+    counts: &[(String, usize)],
+) -> Result<(), String> {
+    let json = loader::load(path)?.json;
+    let counts: HashMap<String, usize> = counts.iter().cloned().collect();
+    let collections = export::generate(&json, names, &counts, seed)?;
+
+    if let Some(out) = out {
+        std::fs::create_dir_all(out).map_err(|err| format!("{}: {}", out, err))?;
+        for (name, data) in &collections {
+            let file = std::path::Path::new(out).join(format!("{}.{}", name, format.ext()));
+            std::fs::write(&file, format.format(name, data))
+                .map_err(|err| format!("{}: {}", file.display(), err))?;
+            eprintln!("{}", file.display());
+        }
+        return Ok(());
+    }
+
+    match (format, collections.as_slice()) {
+        (_, [(name, data)]) => print!("{}", format.format(name, data)),
+        (ExportFormat::Json, _) => {
+            let map: serde_json::Map<String, serde_json::Value> = collections.into_iter().collect();
+            print!("{}", format.format("", &serde_json::Value::Object(map)));
+        }
+        (ExportFormat::Sql, _) => {
+            for (name, data) in &collections {
+                print!("{}", format.format(name, data));
+            }
+        }
+        _ => {
+            return Err(
+                "use --out or --collection to write multiple collections in this format"
+                    .to_string(),
+            )
+        }
+    }
+
+    Ok(())
 }
 
 #[tokio::main]
 async fn main() {
     let args = Args::parse();
 
+    match &args.command {
+        Some(Command::Check { config }) => run_check(config.as_deref().unwrap_or(&args.config)),
+        Some(Command::Generate {
+            config,
+            format,
+            out,
+            collections,
+            counts,
+        }) => {
+            let path = config.as_deref().unwrap_or(&args.config);
+            if let Err(err) = run_generate(
+                path,
+                args.seed,
+                *format,
+                out.as_deref(),
+                collections,
+                counts,
+            ) {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+            return;
+        }
+        None => {}
+    }
+
     Registry::default().with(fmt::layer()).init();
-    tracing::info!("starting json server at port {}", args.port);
+
+    let listens = args
+        .listen
+        .iter()
+        .map(|value| Listen::parse(value, args.port))
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_or_else(|err| {
+            tracing::error!("{}", err);
+            std::process::exit(1);
+        });
 
     let cors = CorsLayer::new()
         .allow_credentials(true)
         .allow_methods(AllowMethods::mirror_request())
         .allow_origin(AllowOrigin::mirror_request())
-        .allow_headers(AllowHeaders::mirror_request());
+        .allow_headers(AllowHeaders::mirror_request())
+        .expose_headers([service::X_TOTAL_COUNT, header::LINK]);
+
+    let mut db = Database::new().with_seed(args.seed);
+    let persister = args.persist.as_deref().map(Persister::new);
+    if let Some(persister) = &persister {
+        db = db.with_persister(persister.clone());
+    }
+
+    let paths = db.init(&args.config).unwrap_or_else(|err| {
+        tracing::error!("failed to load config: {}", err);
+        std::process::exit(1);
+    });
+
+    match db.restore_persisted() {
+        Ok(true) => tracing::info!("loaded the persisted data"),
+        Ok(false) => {}
+        Err(err) => {
+            tracing::error!("failed to load the persisted data: {}", err);
+            std::process::exit(1);
+        }
+    }
+
+    if let Some(persister) = &persister {
+        tokio::spawn(persister.clone().run(db.clone()));
+    }
+
+    let router = AppRouter::new(&db).unwrap_or_else(|err| {
+        tracing::error!("failed to load config: {}", err);
+        std::process::exit(1);
+    });
+    if args.watch {
+        tracing::info!("watching config file {}", args.config);
+        tokio::spawn(watch::watch(args.config, paths, db.clone(), router.clone()));
+    }
+
+    let app = router.service().layer(cors).layer(Extension(db.clone()));
+
+    let tls = match (&args.tls_cert, &args.tls_key) {
+        (Some(cert), Some(key)) => {
+            let config = async {
+                if args.tls_self_signed {
+                    tls::self_signed(cert, key)?;
+                }
+                tls::config(cert, key).await
+            };
+            match config.await {
+                Ok(config) => Some(config),
+                Err(err) => {
+                    tracing::error!("failed to load tls: {}", err);
+                    std::process::exit(1);
+                }
+            }
+        }
+        _ => None,
+    };
+
+    // split the listeners to http and https ones
+    let mut https = Vec::new();
+    let mut http = Vec::new();
+    for listen in listens {
+        match (&tls, args.tls_port) {
+            (Some(_), Some(port)) => {
+                https.extend(listen.with_port(port));
+                http.push(listen);
+            }
+            (Some(_), None) if listen.is_tcp() => https.push(listen),
+            _ => http.push(listen),
+        }
+    }
+
+    let mut servers = Vec::new();
+    for listen in http {
+        tracing::info!("starting json server at http://{}", listen);
+        let app = app.clone();
+        servers.push(tokio::spawn(async move {
+            if let Err(err) = listen.serve(app).await {
+                tracing::error!("failed to serve: {}", err);
+                std::process::exit(1);
+            }
+        }));
+    }
+
+    if let Some(config) = tls {
+        for listen in https {
+            tracing::info!("starting json server at https://{}", listen);
+            let (app, config) = (app.clone(), config.clone());
+            servers.push(tokio::spawn(async move {
+                if let Err(err) = listen.serve_tls(app, config).await {
+                    tracing::error!("failed to serve: {}", err);
+                    std::process::exit(1);
+                }
+            }));
+        }
+    }
+
+    let serve = async {
+        for server in servers {
+            server.await.unwrap();
+        }
+    };
+
+    tokio::select! {
+        _ = serve => {}
+        _ = shutdown_signal() => tracing::info!("shutting down"),
+    }
+
+    // save the changes still waiting for the debounce before exit
+    if let Some(persister) = &persister {
+        match persister.flush(&db).await {
+            Ok(true) => tracing::info!("saved the pending data"),
+            Ok(false) => {}
+            Err(err) => {
+                tracing::error!("failed to save data: {}", err);
+                std::process::exit(1);
+            }
+        }
+    }
+}
 
-    let addr: SocketAddr = format!("127.0.0.1:{}", args.port).parse().unwrap();
-    let mut db = Database::new();
+/// wait for the ctrl-c or the terminate signal
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(err) = tokio::signal::ctrl_c().await {
+            tracing::error!("failed to listen ctrl-c: {}", err);
+            std::future::pending::<()>().await;
+        }
+    };
 
-    db.init(&args.config);
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(err) => {
+                tracing::error!("failed to listen the terminate signal: {}", err);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
 
-    let proxy = app::proxy(&db);
-    let app = app::create(&db)
-        .merge(proxy)
-        .layer(cors)
-        .layer(Extension(db));
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
 
-    axum::Server::bind(&addr)
-        .serve(app.into_make_service())
-        .await
-        .unwrap()
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
 }
@@ -1,3 +1,6 @@
+use super::named_query::is_equal;
+use crate::admin::ADMIN_PATH;
+use crate::util;
 use serde_json::Value;
 use std::collections::HashMap;
 
@@ -15,37 +18,220 @@ pub struct RoutingValue {
     pub wrapping: Option<HashMap<String, Value>>,
     pub rules: Option<Vec<RoutingRule>>,
     pub status: Option<u16>,
+    /// the fixed JSONPath or JMESPath expression to select the result
+    pub select: Option<String>,
+}
+
+/// where to put the pagination info of the page query
+#[derive(Debug, Default, serde::Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PageHeaders {
+    /// only the pagination wrapping in body
+    Body,
+    /// only the `X-Total-Count` and `Link` headers, the body is the items
+    Headers,
+    /// both the headers and the pagination wrapping
+    #[default]
+    Both,
 }
 
 #[derive(Debug, Default)]
 pub struct DataConfig {
     pub routing: HashMap<String, RoutingValue>,
     pub wrapping: HashMap<String, Value>,
-    pub mapping: HashMap<String, String>,
+    pub page_headers: PageHeaders,
+
+    /// the foreign keys of the child collections, such as `{ "comments": { "postId": "posts" } }`
+    pub relations: HashMap<String, HashMap<String, String>>,
+
+    /// the primary key of the collections, default by `id`,
+    /// such as `{ "users": "uuid", "stocks": ["sku", "store"] }`
+    pub keys: HashMap<String, Vec<String>>,
+
+    /// the seed to generate the same data on every start
+    pub seed: Option<u64>,
 }
 
 impl DataConfig {
-    pub fn new(data_config: &Value) -> DataConfig {
+    pub fn new(data_config: &Value) -> Result<DataConfig, String> {
         let mut config = DataConfig::default();
-        config.routing_parse(data_config);
-        config.wrapping_parse(data_config);
+        config.routing_parse(data_config)?;
+        config.wrapping_parse(data_config)?;
+        config.seed_parse(data_config)?;
+        config.page_headers_parse(data_config)?;
+        config.relations_parse(data_config)?;
+        config.keys_parse(data_config)?;
 
-        config
+        Ok(config)
     }
 
-    fn wrapping_parse(&mut self, data: &Value) {
+    fn wrapping_parse(&mut self, data: &Value) -> Result<(), String> {
         let wrap_data = data["wrapping"].clone();
         if !wrap_data.is_null() {
-            self.wrapping = serde_json::from_value::<HashMap<String, Value>>(wrap_data).unwrap();
+            self.wrapping = serde_json::from_value::<HashMap<String, Value>>(wrap_data)
+                .map_err(|err| format!("config.wrapping: {}", err))?;
             //tracing::trace!("config::wrapping  {:?}", self.wrapping)
         }
+
+        Ok(())
+    }
+
+    fn seed_parse(&mut self, data: &Value) -> Result<(), String> {
+        let seed = &data["seed"];
+        if !seed.is_null() {
+            self.seed = Some(
+                seed.as_u64()
+                    .ok_or("config.seed must be non-negative integer")?,
+            );
+        }
+
+        Ok(())
+    }
+
+    fn page_headers_parse(&mut self, data: &Value) -> Result<(), String> {
+        let page_headers = data["page_headers"].clone();
+        if !page_headers.is_null() {
+            self.page_headers = serde_json::from_value(page_headers)
+                .map_err(|err| format!("config.page_headers: {}", err))?;
+        }
+
+        Ok(())
+    }
+
+    fn relations_parse(&mut self, data: &Value) -> Result<(), String> {
+        let relations = data["relations"].clone();
+        if !relations.is_null() {
+            self.relations = serde_json::from_value(relations)
+                .map_err(|err| format!("config.relations: {}", err))?;
+        }
+
+        Ok(())
+    }
+
+    fn keys_parse(&mut self, data: &Value) -> Result<(), String> {
+        let keys = match data["keys"].as_object() {
+            Some(keys) => keys,
+            None if data["keys"].is_null() => return Ok(()),
+            None => return Err("config.keys must be object type".to_string()),
+        };
+
+        for (name, key) in keys {
+            let fields = match key {
+                Value::String(field) => vec![field.clone()],
+                Value::Array(arr) if !arr.is_empty() => arr
+                    .iter()
+                    .map(|field| field.as_str().map(|f| f.to_string()))
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or(format!("config.keys.{} must be array of strings", name))?,
+                _ => {
+                    return Err(format!(
+                        "config.keys.{} must be string or array of strings",
+                        name
+                    ))
+                }
+            };
+            self.keys.insert(name.clone(), fields);
+        }
+
+        Ok(())
+    }
+
+    /// the primary key fields of the collection
+    pub fn primary_key(&self, name: &str) -> Vec<String> {
+        self.keys
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| vec!["id".to_string()])
+    }
+
+    /// the primary key value of the record, the composite key is joined by `,`
+    pub fn key_value(&self, name: &str, item: &Value) -> Option<Value> {
+        let fields = self.primary_key(name);
+        if let [field] = fields.as_slice() {
+            return item.get(field).cloned();
+        }
+
+        let values = fields
+            .iter()
+            .map(|field| item.get(field).map(util::key_string))
+            .collect::<Option<Vec<_>>>()?;
+        Some(Value::String(values.join(",")))
+    }
+
+    /// check the record matches the id by the type of the key,
+    /// the composite id is given by the values joined with `,`, such as `/api/stocks/a1,2`
+    pub fn is_key(&self, name: &str, item: &Value, id: &str) -> bool {
+        let fields = self.primary_key(name);
+        if let [field] = fields.as_slice() {
+            return item.get(field).is_some_and(|value| is_equal(value, id));
+        }
+
+        let parts: Vec<&str> = id.split(',').collect();
+        parts.len() == fields.len()
+            && fields
+                .iter()
+                .zip(parts)
+                .all(|(field, part)| item.get(field).is_some_and(|value| is_equal(value, part)))
+    }
+
+    /// the foreign key in the child collection which references the parent collection,
+    /// default by `<singular>Id`, such as `postId` for `posts`
+    pub fn foreign_key(&self, child: &str, parent: &str) -> String {
+        self.relations
+            .get(child)
+            .and_then(|keys| keys.iter().find(|(_, p)| p.as_str() == parent))
+            .map(|(key, _)| key.clone())
+            .unwrap_or_else(|| format!("{}Id", singular(parent)))
     }
 
-    fn routing_parse(&mut self, data: &Value) {
+    /// the foreign key and parent collection declared for the expand name,
+    /// the name is the foreign key itself or the singular parent name
+    pub fn expand_key(&self, child: &str, name: &str) -> Option<(String, String)> {
+        let keys = self.relations.get(child)?;
+        if let Some(parent) = keys.get(name) {
+            return Some((name.to_string(), parent.clone()));
+        }
+
+        keys.iter()
+            .find(|(_, parent)| singular(parent) == name)
+            .map(|(key, parent)| (key.clone(), parent.clone()))
+    }
+
+    fn routing_parse(&mut self, data: &Value) -> Result<(), String> {
         let routing_data = data["routing"].clone();
         if !routing_data.is_null() {
-            self.routing =
-                serde_json::from_value::<HashMap<String, RoutingValue>>(routing_data).unwrap();
+            self.routing = serde_json::from_value::<HashMap<String, RoutingValue>>(routing_data)
+                .map_err(|err| format!("config.routing: {}", err))?;
         }
+
+        // the router panics with the invalid path, so check it before building
+        for key in self.routing.keys() {
+            if !key.starts_with('/') {
+                return Err(format!(
+                    "config.routing: path '{}' must start with '/'",
+                    key
+                ));
+            }
+
+            if key.starts_with(ADMIN_PATH) {
+                return Err(format!(
+                    "config.routing: path '{}' is reserved for the admin api",
+                    key
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// the singular name of the collection, such as `post` for `posts`
+pub fn singular(name: &str) -> String {
+    if let Some(name) = name.strip_suffix("ies") {
+        format!("{}y", name)
+    } else if let Some(name) = name.strip_suffix('s') {
+        name.to_string()
+    } else {
+        name.to_string()
     }
 }
@@ -13,8 +13,9 @@ pub const WRAP_PAGE_TOTAL: &str = "$total";
 pub const WRAP_PAGE_PAGE: &str = "$page";
 pub const WRAP_PAGE_SIZE: &str = "$size";
 pub const WRAP_PAGE_ITEMS: &str = "$items";
+pub const WRAP_PAGE_NEXT_CURSOR: &str = "$nextCursor";
 
-pub use data_config::{DataConfig, RoutingRule, RoutingValue};
-pub use named_query::NamedQuery;
+pub use data_config::{singular, DataConfig, PageHeaders, RoutingRule, RoutingValue};
+pub use named_query::{encode_cursor, NamedQuery, PageInfo};
 use serde_json::Value;
 pub type Wrapper = HashMap<String, Value>;
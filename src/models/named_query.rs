@@ -1,101 +1,525 @@
-use crate::HashMap;
-use serde_json::Value;
+use crate::util;
+use regex::{Regex, RegexBuilder};
+use serde_json::{Number, Value};
+use std::cmp::Ordering;
+
+/// the filter operator parsed from the query key suffix
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Eq,
+    Ne,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+    Like,
+    In,
+    Contains,
+}
+
+impl Operator {
+    /// split the query key to the field and operator, such as `price_gte`
+    pub fn parse(key: &str) -> (&str, Operator) {
+        if let Some((field, suffix)) = key.rsplit_once('_') {
+            let op = match suffix {
+                "ne" => Some(Operator::Ne),
+                "gt" => Some(Operator::Gt),
+                "gte" => Some(Operator::Gte),
+                "lt" => Some(Operator::Lt),
+                "lte" => Some(Operator::Lte),
+                "like" => Some(Operator::Like),
+                "in" => Some(Operator::In),
+                "contains" => Some(Operator::Contains),
+                _ => None,
+            };
+
+            if let (false, Some(op)) = (field.is_empty(), op) {
+                return (field, op);
+            }
+        }
+
+        (key, Operator::Eq)
+    }
+}
+
+/// the field filter parsed from a non-reserved query key
+#[derive(Debug, Clone)]
+pub struct Filter {
+    pub key: String,
+    pub op: Operator,
+    pub values: Vec<String>,
+    patterns: Vec<Regex>,
+}
+
+impl Filter {
+    pub fn new(key: &str, op: Operator) -> Self {
+        Filter {
+            key: key.to_string(),
+            op,
+            values: Vec::new(),
+            patterns: Vec::new(),
+        }
+    }
+
+    /// add a filter value, the `_in` value is split by comma
+    pub fn push(&mut self, value: &str) -> Result<(), String> {
+        match self.op {
+            Operator::Like => {
+                let re = Regex::new(value).map_err(|err| {
+                    format!("invalid regex '{}' of '{}_like': {}", value, self.key, err)
+                })?;
+                self.patterns.push(re);
+            }
+            Operator::In => self.values.extend(value.split(',').map(|v| v.to_string())),
+            _ => {}
+        }
+        if self.op != Operator::In {
+            self.values.push(value.to_string());
+        }
+
+        Ok(())
+    }
+
+    /// check the item's field matches the filter
+    pub fn is_match(&self, item: &Value) -> bool {
+        let value = match util::get_path(item, &self.key) {
+            Some(value) => value,
+            // the missing field is only not equal to anything
+            None => return self.op == Operator::Ne,
+        };
+
+        match self.op {
+            Operator::Eq | Operator::In => self.values.iter().any(|v| is_equal(value, v)),
+            Operator::Ne => !self.values.iter().any(|v| is_equal(value, v)),
+            Operator::Gt => self.is_order(value, |o| o == Ordering::Greater),
+            Operator::Gte => self.is_order(value, |o| o != Ordering::Less),
+            Operator::Lt => self.is_order(value, |o| o == Ordering::Less),
+            Operator::Lte => self.is_order(value, |o| o != Ordering::Greater),
+            Operator::Like => {
+                let text = match value {
+                    Value::String(s) => s.clone(),
+                    v => v.to_string(),
+                };
+                self.patterns.iter().any(|re| re.is_match(&text))
+            }
+            Operator::Contains => match value {
+                Value::Array(arr) => arr
+                    .iter()
+                    .any(|item| self.values.iter().any(|v| is_equal(item, v))),
+                Value::String(s) => self.values.iter().any(|v| s.contains(v.as_str())),
+                _ => false,
+            },
+        }
+    }
+
+    // compare the field with all the values, the value is converted to the field's type
+    fn is_order<F: Fn(Ordering) -> bool>(&self, value: &Value, f: F) -> bool {
+        self.values.iter().all(|v| match to_typed(value, v) {
+            Some(v) => f(util::cmp(value, &v)),
+            None => false,
+        })
+    }
+}
+
+/// convert the query text to the same type as the json value
+pub fn to_typed(value: &Value, text: &str) -> Option<Value> {
+    match value {
+        Value::Bool(_) => text.parse::<bool>().ok().map(Value::Bool),
+        Value::Number(_) => match text.parse::<i64>() {
+            Ok(v) => Some(Value::from(v)),
+            Err(_) => text
+                .parse::<f64>()
+                .ok()
+                .and_then(Number::from_f64)
+                .map(Value::Number),
+        },
+        Value::String(_) => Some(Value::String(text.to_string())),
+        _ => None,
+    }
+}
+
+/// compare the json value with the query text by the value's type
+pub fn is_equal(value: &Value, text: &str) -> bool {
+    match value {
+        Value::Bool(value) => text.parse::<bool>() == Ok(*value),
+        Value::Number(value) => {
+            if let (Some(value), Ok(v)) = (value.as_i64(), text.parse::<i64>()) {
+                return value == v;
+            }
+            if let (Some(value), Ok(v)) = (value.as_u64(), text.parse::<u64>()) {
+                return value == v;
+            }
+            match (value.as_f64(), text.parse::<f64>()) {
+                (Some(value), Ok(v)) => value == v,
+                _ => false,
+            }
+        }
+        Value::String(value) => value == text,
+        Value::Null => text == "null",
+        _ => false,
+    }
+}
+
+/// the page info of the page query
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageInfo {
+    pub page: usize,
+    pub size: usize,
+    pub total: usize,
+}
+
+impl PageInfo {
+    /// the last page index, at least 1
+    pub fn last(&self) -> usize {
+        std::cmp::max(1, self.total.div_ceil(self.size))
+    }
+}
+
+/// the sort key parsed from `_sort`, `_order` and `_nulls`
+#[derive(Debug, Clone)]
+pub struct SortKey {
+    pub path: String,
+    pub desc: bool,
+    pub nulls_first: bool,
+}
+
+impl SortKey {
+    /// compare the items by the key, the null or missing field is placed by `nulls_first`
+    pub fn cmp(&self, a: &Value, b: &Value) -> Ordering {
+        let a = util::get_path(a, &self.path).unwrap_or(&Value::Null);
+        let b = util::get_path(b, &self.path).unwrap_or(&Value::Null);
+
+        match (a.is_null(), b.is_null()) {
+            (true, true) => Ordering::Equal,
+            (true, false) if self.nulls_first => Ordering::Less,
+            (true, false) => Ordering::Greater,
+            (false, true) if self.nulls_first => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            _ if self.desc => util::cmp(b, a),
+            _ => util::cmp(a, b),
+        }
+    }
+}
 
 #[derive(Default, Clone)]
 pub struct NamedQuery {
-    pub page: Option<u32>,
-    pub size: Option<u32>,
-    pub search: Option<String>,
-    pub id: Option<String>,
+    pub page: Option<usize>,
+    pub size: Option<usize>,
+
+    pub start: Option<usize>,
+    pub end: Option<usize>,
+    pub limit: Option<usize>,
+    /// the item offset decoded from `_cursor`
+    pub cursor: Option<usize>,
+    /// the compiled `_q` pattern
+    pub search: Option<Regex>,
+    /// the fields to search, search all fields if it is empty
+    pub search_fields: Vec<String>,
+
+    pub sort: Vec<SortKey>,
+
+    /// the fields to keep in the result
+    pub fields: Vec<String>,
+    /// the fields to remove from the result
+    pub exclude: Vec<String>,
 
-    pub sort: Option<String>,
-    pub order: Option<String>,
+    /// count the items
+    pub count: bool,
+    /// group the items by the field
+    pub group_by: Option<String>,
+    /// the fields to sum, average, and find the min or max
+    pub sum: Vec<String>,
+    pub avg: Vec<String>,
+    pub min: Vec<String>,
+    pub max: Vec<String>,
+    /// the fields to count the distinct values
+    pub facet: Vec<String>,
 
-    query: HashMap<String, String>,
+    /// the JSONPath or JMESPath expression to select the result
+    pub select: Option<String>,
+
+    /// the child collections to embed
+    pub embed: Vec<String>,
+    /// the parent records to expand
+    pub expand: Vec<String>,
+
+    pub filters: Vec<Filter>,
+    /// the non-reserved query pairs which the filters are built from
+    params: Vec<(String, String)>,
 }
 
 impl NamedQuery {
-    /// convert hashmap query to named query
-    pub fn from(query: &HashMap<String, String>) -> Self {
+    /// convert query pairs to named query, the repeated keys are merged
+    pub fn from(query: &[(String, String)]) -> Result<Self, String> {
         let mut named_query = NamedQuery::default();
-        named_query.query = query.clone();
-        // page index
-        if let Some(page) = query.get("_page") {
-            named_query.page = Some(page.parse().unwrap());
-            named_query.query.remove("_page");
+        let mut sort = Vec::new();
+        let mut order = Vec::new();
+        let mut nulls = Vec::new();
+        let mut search = None;
+        let mut search_mode = None;
+        let mut search_case = None;
+        for (key, value) in query {
+            match key.as_str() {
+                // page index
+                "_page" => named_query.page = Some(parse_num(key, value, 1)?),
+                // page size
+                "_size" => named_query.size = Some(parse_num(key, value, 1)?),
+                // slice start index
+                "_start" => named_query.start = Some(parse_num(key, value, 0)?),
+                // slice end index, exclusive
+                "_end" => named_query.end = Some(parse_num(key, value, 0)?),
+                // slice or cursor page length
+                "_limit" => named_query.limit = Some(parse_num(key, value, 1)?),
+                // the opaque cursor, the empty value starts from the first item
+                "_cursor" => named_query.cursor = Some(decode_cursor(value)?),
+                // common search keyword
+                "_q" => search = Some(value.as_str()),
+                // search mode by regex or plain
+                "_q_mode" => search_mode = Some(value.as_str()),
+                // search case by sensitive or insensitive
+                "_q_case" => search_case = Some(value.as_str()),
+                // search fields
+                "_q_fields" => named_query
+                    .search_fields
+                    .extend(split(value).map(|v| v.to_string())),
+                // projection fields
+                "_fields" => named_query
+                    .fields
+                    .extend(split(value).map(|v| v.to_string())),
+                "_exclude" => named_query
+                    .exclude
+                    .extend(split(value).map(|v| v.to_string())),
+                // aggregation
+                "_count" => named_query.count = true,
+                "_group_by" => named_query.group_by = Some(value.clone()),
+                "_sum" => named_query.sum.extend(split(value).map(|v| v.to_string())),
+                "_avg" => named_query.avg.extend(split(value).map(|v| v.to_string())),
+                "_min" => named_query.min.extend(split(value).map(|v| v.to_string())),
+                "_max" => named_query.max.extend(split(value).map(|v| v.to_string())),
+                "_facet" => named_query
+                    .facet
+                    .extend(split(value).map(|v| v.to_string())),
+                // selection
+                "_select" => named_query.select = Some(value.clone()),
+                // relations
+                "_embed" => named_query
+                    .embed
+                    .extend(split(value).map(|v| v.to_string())),
+                "_expand" => named_query
+                    .expand
+                    .extend(split(value).map(|v| v.to_string())),
+                // sort keys, such as `name,-age`
+                "_sort" => sort.extend(split(value)),
+                // sort orders matching the sort keys
+                "_order" => order.extend(split(value)),
+                // nulls position matching the sort keys
+                "_nulls" => nulls.extend(split(value)),
+                _ => named_query.params.push((key.clone(), value.clone())),
+            }
         }
+        // check the filter values before the items are known
+        named_query.filter_by(&[])?;
 
-        // page size
-        if let Some(size) = query.get("_size") {
-            named_query.size = Some(size.parse().unwrap());
-            named_query.query.remove("_size");
+        if let Some(q) = search {
+            let pattern = match search_mode {
+                Some("regex") | None => q.to_string(),
+                Some("plain") => regex::escape(q),
+                Some(v) => return Err(format!("invalid _q_mode '{}'", v)),
+            };
+            let case_insensitive = match search_case {
+                Some("sensitive") | None => false,
+                Some("insensitive") => true,
+                Some(v) => return Err(format!("invalid _q_case '{}'", v)),
+            };
+            let re = RegexBuilder::new(&pattern)
+                .case_insensitive(case_insensitive)
+                .build()
+                .map_err(|err| format!("invalid _q '{}': {}", q, err))?;
+            named_query.search = Some(re);
         }
 
-        // common search keyword
-        if let Some(search) = query.get("_q") {
-            named_query.search = Some(search.clone());
-            named_query.query.remove("_q");
-        }
+        for (index, key) in sort.into_iter().enumerate() {
+            let (path, desc) = match key.strip_prefix('-') {
+                Some(path) => (path, true),
+                None => match list_get(&order, index) {
+                    Some("desc") => (key, true),
+                    Some("asc") | None => (key, false),
+                    Some(v) => return Err(format!("invalid _order '{}'", v)),
+                },
+            };
+
+            let nulls_first = match list_get(&nulls, index) {
+                Some("first") => true,
+                Some("last") | None => false,
+                Some(v) => return Err(format!("invalid _nulls '{}'", v)),
+            };
 
-        // sort
-        if let Some(sort) = query.get("_sort") {
-            named_query.sort = Some(sort.clone());
-            named_query.query.remove("_sort");
+            named_query.sort.push(SortKey {
+                path: path.to_string(),
+                desc,
+                nulls_first,
+            });
         }
 
-        // order
-        if let Some(order) = query.get("_order") {
-            named_query.order = Some(order.clone());
-            named_query.query.remove("_order");
+        Ok(named_query)
+    }
+
+    /// build the filters by the fields of the items, the key which is an item field is
+    /// matched by equal even it ends with an operator, such as `sign_in`, and the unknown
+    /// key starting with `_` is skipped, such as the cache buster `_=12345`
+    pub fn filter_by(&mut self, items: &[Value]) -> Result<(), String> {
+        let has_field = |key: &str| items.iter().any(|item| util::get_path(item, key).is_some());
+
+        let mut filters: Vec<Filter> = Vec::new();
+        for (key, value) in &self.params {
+            // the exact field takes precedence over the operator suffix
+            let (key, op) = match has_field(key) {
+                true => (key.as_str(), Operator::Eq),
+                false => Operator::parse(key),
+            };
+            if key.starts_with('_') && !has_field(key) {
+                continue;
+            }
+
+            let index = match filters.iter().position(|f| f.key == key && f.op == op) {
+                Some(index) => index,
+                None => {
+                    filters.push(Filter::new(key, op));
+                    filters.len() - 1
+                }
+            };
+            filters[index].push(value)?;
         }
 
-        named_query
+        self.filters = filters;
+        Ok(())
+    }
+
+    /// check if the query aggregates the items instead of listing them
+    pub fn is_aggregate(&self) -> bool {
+        self.count
+            || self.group_by.is_some()
+            || !self.sum.is_empty()
+            || !self.avg.is_empty()
+            || !self.min.is_empty()
+            || !self.max.is_empty()
+            || !self.facet.is_empty()
     }
 
-    /// get value from query by given key
-    // pub fn get(&self, key: &str) -> Option<&String> {
-    //     self.query.get(key)
-    // }
+    /// project the item by `_fields` and `_exclude`
+    pub fn project(&self, item: Value) -> Value {
+        let mut item = if self.fields.is_empty() {
+            item
+        } else {
+            let mut ret = Value::Object(Default::default());
+            for field in &self.fields {
+                if let Some(value) = util::pick_path(&item, field) {
+                    util::merge(&mut ret, value);
+                }
+            }
+            ret
+        };
+
+        for field in &self.exclude {
+            util::remove_path(&mut item, field);
+        }
+
+        item
+    }
 
-    /// check if the query is empty
-    // pub fn is_empty(&self) -> bool {
-    //     self.query.is_empty()
-    // }
+    /// compare the items by the sort keys in order
+    pub fn cmp(&self, a: &Value, b: &Value) -> Ordering {
+        self.sort
+            .iter()
+            .map(|key| key.cmp(a, b))
+            .find(|o| o.is_ne())
+            .unwrap_or(Ordering::Equal)
+    }
 
     /// check thie item's properties matches the query
     pub fn is_match(&self, item: &Value) -> bool {
-        let mut ret = true;
-
-        // for (k, v) in &self.query {
-        //   ret &= match &item[k] {
-        //     Value::Bool(value) => *value == v.parse::<bool>().unwrap(),
-        //     Value::Number(value) => value.as_f64().unwrap() == v.parse::<f64>().unwrap(),
-        //     Value::String(value) => value == v,
-        //     _ => false,
-        //   };
-        // }
-
-        // continue to match the _q query
-        if ret {
-            let mut is_match_any = false;
-            if let Some(q) = &self.search {
-                for (_, v) in item.as_object().unwrap() {
-                    if v.is_string() {
-                        let is_match = regex::Regex::new(&q).unwrap().is_match(v.as_str().unwrap());
-                        if is_match {
-                            is_match_any = true;
-                            break;
-                        }
-                    }
-                }
-            } else {
-                // if there is no _q query,  set to true
-                is_match_any = true;
-            }
+        if !self.filters.iter().all(|f| f.is_match(item)) {
+            return false;
+        }
 
-            ret = is_match_any;
+        // continue to match the _q query, if there is no _q query, set to true
+        let re = match &self.search {
+            Some(re) => re,
+            None => return true,
+        };
+        if self.search_fields.is_empty() {
+            return is_search_match(re, item);
         }
+        self.search_fields
+            .iter()
+            .filter_map(|field| util::get_path(item, field))
+            .any(|value| is_search_match(re, value))
+    }
+}
+
+// match the string and number values in the item and its nested objects and arrays
+fn is_search_match(re: &Regex, value: &Value) -> bool {
+    match value {
+        Value::String(s) => re.is_match(s),
+        Value::Number(n) => re.is_match(&n.to_string()),
+        Value::Object(obj) => obj.values().any(|v| is_search_match(re, v)),
+        Value::Array(arr) => arr.iter().any(|v| is_search_match(re, v)),
+        _ => false,
+    }
+}
+
+// split the comma separated list
+fn split(value: &str) -> impl Iterator<Item = &str> {
+    value.split(',').map(|v| v.trim()).filter(|v| !v.is_empty())
+}
 
-        ret
+// get the list value by index, the single value applies to all
+fn list_get<'a>(list: &[&'a str], index: usize) -> Option<&'a str> {
+    match list.len() {
+        1 => Some(list[0]),
+        _ => list.get(index).copied(),
     }
 }
+
+// parse the paging number which must not be less than min
+fn parse_num(key: &str, value: &str, min: usize) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(v) if v >= min => Ok(v),
+        _ => Err(format!(
+            "invalid {} '{}', must be an integer not less than {}",
+            key, value, min
+        )),
+    }
+}
+
+const CURSOR_PREFIX: &str = "offset:";
+
+/// encode the item offset to an opaque cursor
+pub fn encode_cursor(offset: usize) -> String {
+    format!("{}{}", CURSOR_PREFIX, offset)
+        .bytes()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// decode the item offset from the cursor
+fn decode_cursor(cursor: &str) -> Result<usize, String> {
+    if cursor.is_empty() {
+        return Ok(0);
+    }
+
+    let err = || format!("invalid _cursor '{}'", cursor);
+    if !cursor.len().is_multiple_of(2) || !cursor.is_ascii() {
+        return Err(err());
+    }
+    let bytes = (0..cursor.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&cursor[i..i + 2], 16))
+        .collect::<Result<Vec<u8>, _>>()
+        .map_err(|_| err())?;
+
+    String::from_utf8(bytes)
+        .ok()
+        .and_then(|text| text.strip_prefix(CURSOR_PREFIX)?.parse().ok())
+        .ok_or_else(err)
+}
@@ -0,0 +1,111 @@
+use crate::Database;
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    io::{BufReader, BufWriter, Write},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::sync::{Mutex, Notify};
+
+/// wait for a while to save the changes together
+const SAVE_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// persist the collections to the file when they are changed
+#[derive(Clone)]
+pub struct Persister {
+    path: PathBuf,
+    notify: Arc<Notify>,
+    /// the changes are not saved yet
+    pending: Arc<AtomicBool>,
+    /// only one save runs at a time
+    saving: Arc<Mutex<()>>,
+}
+
+impl Persister {
+    pub fn new(path: &str) -> Self {
+        Persister {
+            path: PathBuf::from(path),
+            notify: Arc::new(Notify::new()),
+            pending: Arc::new(AtomicBool::new(false)),
+            saving: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// load the saved collections, return none if the file does not exist
+    pub fn load(&self) -> Result<Option<HashMap<String, Value>>, String> {
+        if !self.path.exists() {
+            return Ok(None);
+        }
+
+        let file = std::fs::File::open(&self.path)
+            .map_err(|err| format!("{}: {}", self.path.display(), err))?;
+        let reader = BufReader::new(file);
+        serde_json::from_reader(reader)
+            .map(Some)
+            .map_err(|err| format!("{}: {}", self.path.display(), err))
+    }
+
+    /// write the collections to a temp file and rename it to the target
+    /// so the saved file is never left half written
+    pub fn save(&self, collections: &HashMap<String, Value>) -> Result<(), String> {
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(".tmp");
+        let tmp = PathBuf::from(tmp);
+
+        let write = || -> std::io::Result<()> {
+            let mut writer = BufWriter::new(std::fs::File::create(&tmp)?);
+            serde_json::to_writer_pretty(&mut writer, collections)?;
+            writer.flush()?;
+            std::fs::rename(&tmp, &self.path)
+        };
+
+        write().map_err(|err| format!("{}: {}", self.path.display(), err))
+    }
+
+    /// mark the collections changed
+    pub fn changed(&self) {
+        self.pending.store(true, Ordering::SeqCst);
+        self.notify.notify_one();
+    }
+
+    /// save the pending changes without waiting, it is called before the server exits,
+    /// return false if there is nothing to save
+    pub async fn flush(&self, db: &Database) -> Result<bool, String> {
+        // wait for the running save so the latest changes are not lost
+        let _saving = self.saving.lock().await;
+        if !self.pending.swap(false, Ordering::SeqCst) {
+            return Ok(false);
+        }
+
+        let collections = db.snapshot();
+        let persister = self.clone();
+        let result = tokio::task::spawn_blocking(move || persister.save(&collections))
+            .await
+            .unwrap_or_else(|err| Err(err.to_string()));
+        if result.is_err() {
+            // keep the changes to save on the next time
+            self.pending.store(true, Ordering::SeqCst);
+        }
+
+        result.map(|_| true)
+    }
+
+    /// save the db collections after every change
+    pub async fn run(self, db: Database) {
+        loop {
+            self.notify.notified().await;
+            tokio::time::sleep(SAVE_DEBOUNCE).await;
+
+            match self.flush(&db).await {
+                Ok(true) => tracing::debug!("saved data to {}", self.path.display()),
+                Ok(false) => {}
+                Err(err) => tracing::error!("failed to save data: {}", err),
+            }
+        }
+    }
+}
@@ -1,18 +1,27 @@
-use crate::models::Wrapper;
+use crate::db::{CreateError, Patch};
+use crate::models::{NamedQuery, PageInfo, Wrapper};
+use crate::util;
 use crate::Database;
 use crate::HashMap;
 use axum::body::StreamBody;
 use axum::extract::Path;
 use axum::extract::Query;
 use axum::http::header;
+use axum::http::header::HeaderName;
+use axum::http::HeaderMap;
+use axum::http::HeaderValue;
 use axum::http::StatusCode;
+use axum::http::Uri;
 use axum::response::IntoResponse;
+use axum::response::Response;
 use axum::Extension;
 use axum::Json;
 use regex::Regex;
 use serde_json::Value;
 use tokio_util::io::ReaderStream;
 
+pub const X_TOTAL_COUNT: HeaderName = HeaderName::from_static("x-total-count");
+
 macro_rules! wrapping {
     ($result: expr) => {
         $crate::util::wrap_result($result, None)
@@ -25,18 +34,78 @@ macro_rules! wrapping {
 
 pub async fn query_data(
     Path(path_map): Path<HashMap<String, String>>,
-    Query(query): Query<HashMap<String, String>>,
+    Query(query): Query<Vec<(String, String)>>,
     Extension(db): Extension<Database>,
+    uri: Uri,
 ) -> impl IntoResponse {
-    wrapping!(db.query_data(&path_map, &query))
+    query_page(&path_map, &query, &db, &uri, "_page")
+}
+
+/// query data with the page headers, the `page_key` is the page index key in the uri query
+pub fn query_page(
+    path_map: &HashMap<String, String>,
+    query: &[(String, String)],
+    db: &Database,
+    uri: &Uri,
+    page_key: &str,
+) -> Response {
+    // the malformed query, such as `_page=0` or an invalid `_cursor`, is a bad request
+    if let Err(err) = NamedQuery::from(query) {
+        return (StatusCode::BAD_REQUEST, wrapping!(Err(err))).into_response();
+    }
+
+    match db.query_data(path_map, query) {
+        Ok((value, info)) => {
+            let headers = info
+                .map(|info| page_headers(uri, page_key, &info))
+                .unwrap_or_default();
+            (headers, wrapping!(Ok(value))).into_response()
+        }
+        Err(err) => wrapping!(Err(err)).into_response(),
+    }
+}
+
+/// create the `X-Total-Count` and `Link` headers
+fn page_headers(uri: &Uri, page_key: &str, info: &PageInfo) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(X_TOTAL_COUNT, HeaderValue::from(info.total));
+
+    let query: Vec<(String, String)> =
+        serde_urlencoded::from_str(uri.query().unwrap_or_default()).unwrap_or_default();
+    let link = |page: usize, rel: &str| {
+        let mut query = query.clone();
+        match query.iter_mut().find(|(key, _)| key == page_key) {
+            Some((_, value)) => *value = page.to_string(),
+            None => query.push((page_key.to_string(), page.to_string())),
+        }
+        let query = serde_urlencoded::to_string(&query).unwrap_or_default();
+        format!("<{}?{}>; rel=\"{}\"", uri.path(), query, rel)
+    };
+
+    let last = info.last();
+    let mut links = vec![link(1, "first")];
+    if info.page > 1 {
+        links.push(link(std::cmp::min(info.page - 1, last), "prev"));
+    }
+    if info.page < last {
+        links.push(link(info.page + 1, "next"));
+    }
+    links.push(link(last, "last"));
+
+    if let Ok(value) = HeaderValue::from_str(&links.join(", ")) {
+        headers.insert(header::LINK, value);
+    }
+
+    headers
 }
 
 pub async fn get_data(
     Path(path_map): Path<HashMap<String, String>>,
+    Query(query): Query<Vec<(String, String)>>,
     Extension(db): Extension<Database>,
     Extension(wrap): Extension<Wrapper>,
 ) -> impl IntoResponse {
-    wrapping!(db.get_data(&path_map), wrap)
+    wrapping!(db.get_data(&path_map, &query), wrap)
 }
 
 pub async fn post_data(
@@ -45,7 +114,42 @@ pub async fn post_data(
     Extension(mut db): Extension<Database>,
     Extension(wrap): Extension<Wrapper>,
 ) -> impl IntoResponse {
-    wrapping!(db.create_data(&path_map, body), wrap)
+    wrapping!(
+        db.create_data(&path_map, body)
+            .map_err(|err| err.to_string()),
+        wrap
+    )
+}
+
+/// create the record in the collection, returns 201 with the `Location` of the record
+pub async fn create_data(
+    Path(path_map): Path<HashMap<String, String>>,
+    Json(body): Json<Value>,
+    Extension(mut db): Extension<Database>,
+    Extension(wrap): Extension<Wrapper>,
+) -> Response {
+    match db.create_data(&path_map, body) {
+        Ok(value) => {
+            let data_name = path_map.get("child").unwrap_or(&path_map["data"]);
+            let id = db
+                .get_config()
+                .key_value(data_name, &value)
+                .map(|v| util::key_string(&v))
+                .unwrap_or_default();
+            let location = match (path_map.get("id"), path_map.get("child")) {
+                (Some(parent_id), Some(child)) => {
+                    format!("/api/{}/{}/{}/{}", path_map["data"], parent_id, child, id)
+                }
+                _ => format!("/api/{}/{}", path_map["data"], id),
+            };
+            let headers = [(header::LOCATION, location)];
+            (StatusCode::CREATED, headers, wrapping!(Ok(value), wrap)).into_response()
+        }
+        Err(CreateError::Conflict(err)) => {
+            (StatusCode::CONFLICT, wrapping!(Err(err), wrap)).into_response()
+        }
+        Err(err) => wrapping!(Err(err.to_string()), wrap).into_response(),
+    }
 }
 
 pub async fn put_data(
@@ -54,7 +158,38 @@ pub async fn put_data(
     Extension(mut db): Extension<Database>,
     Extension(wrap): Extension<Wrapper>,
 ) -> impl IntoResponse {
-    wrapping!(db.update_data(&query, body), wrap)
+    write_response(db.update_data(&query, body), wrap)
+}
+
+pub async fn patch_data(
+    Path(path_map): Path<HashMap<String, String>>,
+    headers: HeaderMap,
+    Json(body): Json<Value>,
+    Extension(mut db): Extension<Database>,
+    Extension(wrap): Extension<Wrapper>,
+) -> impl IntoResponse {
+    // the json patch by content type, otherwise the merge patch
+    let is_json_patch = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json-patch+json"));
+    let patch = if is_json_patch {
+        Patch::Json(body)
+    } else {
+        Patch::Merge(body)
+    };
+
+    write_response(db.patch_data(&path_map, patch), wrap)
+}
+
+/// the conflicting key is responded with 409
+fn write_response(result: Result<Value, CreateError>, wrap: Wrapper) -> Response {
+    match result {
+        Err(CreateError::Conflict(err)) => {
+            (StatusCode::CONFLICT, wrapping!(Err(err), wrap)).into_response()
+        }
+        result => wrapping!(result.map_err(|err| err.to_string()), wrap).into_response(),
+    }
 }
 
 pub async fn delete_data(
@@ -92,7 +227,7 @@ pub async fn get_file(
                 }
 
                 if Regex::new("txt").unwrap().is_match(ext) {
-                    content_type = format!("text/plain; charset=utf-8");
+                    content_type = "text/plain; charset=utf-8".to_string();
                 }
             }
 
@@ -100,6 +235,6 @@ pub async fn get_file(
             Ok((headers, body))
         }
 
-        Err(err) => return Err((StatusCode::NOT_FOUND, err)),
+        Err(err) => Err((StatusCode::NOT_FOUND, err)),
     }
 }
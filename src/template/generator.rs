@@ -1,5 +1,7 @@
 use crate::template::call;
+use crate::template::get_fn_mapping;
 use crate::template::utils;
+use once_cell::sync::Lazy;
 use regex::Regex;
 use serde_json::json;
 use serde_json::Map;
@@ -33,13 +35,85 @@ pub struct Rule {
     pub dcount: Option<usize>,
 }
 
+/// the rule part of the name, the same as the rule part of `parse_rule`
+static RE_RULE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(?:\+(\d+)|(\d+)(?:-(\d+))?(?:\.(\d+)(?:-(\d+))?)?|\.(\d+)(?:-(\d+))?)$").unwrap()
+});
+
+/// 1 function name  2 function parameters
+static RE_FN: Lazy<Regex> = Lazy::new(|| Regex::new(r"@([^@#%&()\?\s]+)(?:\((.*?)\))?").unwrap());
+
 impl Generator {
     pub fn new() -> Self {
         Self
     }
 
+    /// check the rule of the name, such as `name|1-10`
+    /// the name without rule is always valid
+    pub fn check_rule(&self, name: &str) -> Result<(), String> {
+        let rule = match name.rfind('|') {
+            Some(index) => &name[index + 1..],
+            None => return Ok(()),
+        };
+
+        if name.starts_with('|') {
+            return Err(format!("missing name before the rule '{}'", rule));
+        }
+
+        let cap = RE_RULE.captures(rule).ok_or(format!(
+            "invalid rule '{}', expect `count`, `min-max`, `min-max.dmin-dmax` or `+step`",
+            rule
+        ))?;
+
+        // the numbers must fit usize, and min must not be greater than max
+        let mut numbers = Vec::new();
+        for index in 1..cap.len() {
+            let number = match cap.get(index) {
+                Some(m) => Some(
+                    m.as_str()
+                        .parse::<usize>()
+                        .map_err(|err| format!("invalid number in rule '{}': {}", rule, err))?,
+                ),
+                None => None,
+            };
+            numbers.push(number);
+        }
+
+        for (min, max) in [(1, 2), (3, 4), (5, 6)] {
+            if let (Some(min), Some(max)) = (numbers[min], numbers[max]) {
+                if min > max {
+                    return Err(format!("min is greater than max in rule '{}'", rule));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// check the function call in the string value, such as `@word(1, 10)`
+    pub fn check_string(&self, value: &str) -> Result<(), String> {
+        if let Some(cap) = RE_FN.captures(value) {
+            let fn_name = cap.get(1).map(|m| m.as_str()).unwrap();
+            if !get_fn_mapping().contains_key(fn_name) {
+                return Err(format!("unknown function '@{}'", fn_name));
+            }
+
+            if let Some(p) = cap.get(2).map(|m| m.as_str()) {
+                let params: Vec<&str> = p.split(',').map(|item| item.trim()).collect();
+                if params.len() > 2 || params.iter().any(|p| p.parse::<usize>().is_err()) {
+                    return Err(format!(
+                        "invalid parameters '({})' of '@{}', expect `(count)` or `(min, max)`",
+                        p, fn_name
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     /// generate data from name and value
-    pub fn gen_data(&self, name: &str, value: &Value) -> (String, Value) {
+    pub fn gen_data(&self, name: &str, value: &Value) -> Result<(String, Value), String> {
         let mut context = GeneratorContext::default();
         self.gen_data_with_context(name, value, &mut context)
     }
@@ -49,18 +123,18 @@ impl Generator {
         name: &str,
         value: &Value,
         context: &mut GeneratorContext,
-    ) -> (String, Value) {
-        let rule = self.parse_rule(name);
+    ) -> Result<(String, Value), String> {
+        let rule = self.parse_rule(name)?;
         let data = match value {
-            Value::Array(v) => self.array(&rule, v, context),
-            Value::Object(v) => self.object(&rule, v, context),
+            Value::Array(v) => self.array(&rule, v, context)?,
+            Value::Object(v) => self.object(&rule, v, context)?,
             Value::Number(v) => self.number(&rule, v, context),
-            Value::String(v) => self.string(&rule, v, context),
+            Value::String(v) => self.string(&rule, v, context)?,
             Value::Bool(v) => self.bool(&rule, v, context),
             _ => value.clone(),
         };
 
-        (rule.name, data)
+        Ok((rule.name, data))
     }
 
     /// name rule
@@ -74,14 +148,19 @@ impl Generator {
     /// name|count.dcount': value
     /// name|+step': value
     /// 1 name, 2 step, 3 range [ min, max ], 4 drange [ dmin, dmax ]
-    pub fn parse_rule<'a>(&self, name: &'a str) -> Rule {
+    /// the rule is checked first, so the invalid rule is an error instead of panic
+    pub fn parse_rule(&self, name: &str) -> Result<Rule, String> {
+        self.check_rule(name)?;
+
         let re_name =
             Regex::new(r"(.+)\|(?:\+(\d+)|([\+\-]?\d+-?[\+\-]?\d*)?(?:\.(\d+-?\d*))?)").unwrap();
         let re_range = Regex::new(r"([\+\-]?\d+)-?([\+\-]?\d+)?").unwrap();
         let capture = re_name.captures(name);
-        let mut rule = Rule::default();
+        let mut rule = Rule {
+            name: name.to_string(),
+            ..Default::default()
+        };
 
-        rule.name = name.to_string();
         if let Some(cap) = capture {
             // real name
             rule.name = cap.get(1).map(|m| m.as_str()).unwrap().to_string();
@@ -107,8 +186,8 @@ impl Generator {
                         rule.max = Some(max.as_str().parse::<usize>().unwrap());
                     }
                     // caculate count
-                    if rule.min.is_some() && rule.max.is_some() {
-                        rule.count = Some(utils::random(rule.min.unwrap(), rule.max.unwrap()));
+                    if let (Some(min), Some(max)) = (rule.min, rule.max) {
+                        rule.count = Some(utils::random(min, max));
                     } else if rule.min.is_some() {
                         rule.count = rule.min;
                     }
@@ -128,40 +207,51 @@ impl Generator {
                         rule.dmax = Some(dmax.as_str().parse::<usize>().unwrap());
                     }
                     // caculate dcount
-                    if rule.dmin.is_some() && rule.dmax.is_some() {
-                        rule.dcount = Some(utils::random(rule.dmin.unwrap(), rule.dmax.unwrap()));
+                    if let (Some(dmin), Some(dmax)) = (rule.dmin, rule.dmax) {
+                        rule.dcount = Some(utils::random(dmin, dmax));
                     } else if rule.dmin.is_some() {
                         rule.dcount = rule.dmin;
                     }
                 }
             }
         }
-        rule
+        Ok(rule)
     }
 
-    fn array(&self, rule: &Rule, data: &Vec<Value>, context: &mut GeneratorContext) -> Value {
+    fn array(
+        &self,
+        rule: &Rule,
+        data: &Vec<Value>,
+        context: &mut GeneratorContext,
+    ) -> Result<Value, String> {
         let mut result: Vec<Value> = Vec::new();
+        if rule.is_rule && data.is_empty() {
+            return Err(format!(
+                "{}: the array with rule must not be empty",
+                rule.name
+            ));
+        }
 
         if !rule.is_rule {
             let mut context = context.clone();
             for (i, v) in data.iter().enumerate() {
                 context.inc = i;
-                let (_, d) = self.gen_data_with_context(&i.to_string(), v, &mut context);
+                let (_, d) = self.gen_data_with_context(&i.to_string(), v, &mut context)?;
                 result.push(d);
             }
         } else {
             if rule.is_range && rule.min.unwrap() == 1 && rule.max.is_none() {
                 // just return value, not the array type
-                return json!(utils::pick(data).clone());
+                return Ok(json!(utils::pick(data).clone()));
             } else if rule.is_step {
                 let index = context.order_index % data.len();
                 let value = data.get(index).unwrap();
 
-                let (_, v) = self.gen_data_with_context("", value, context);
+                let (_, v) = self.gen_data_with_context("", value, context)?;
                 context.order_index += 1;
 
                 // direct return the value, not the array
-                return v;
+                return Ok(v);
             } else {
                 let mut context = context.clone();
                 let count = rule.count.unwrap();
@@ -169,7 +259,7 @@ impl Generator {
                 for _ in 0..count {
                     for v in data {
                         let name = result.len().to_string();
-                        let (_, value) = self.gen_data_with_context(&name, v, &mut context);
+                        let (_, value) = self.gen_data_with_context(&name, v, &mut context)?;
                         result.push(value);
 
                         context.inc += 1;
@@ -178,7 +268,7 @@ impl Generator {
             }
         }
 
-        Value::Array(result)
+        Ok(Value::Array(result))
     }
 
     fn object(
@@ -186,14 +276,14 @@ impl Generator {
         _rule: &Rule,
         data: &Map<String, Value>,
         context: &mut GeneratorContext,
-    ) -> Value {
+    ) -> Result<Value, String> {
         let mut result = Map::new();
         for (name, value) in data {
-            let (n, v) = self.gen_data_with_context(name, value, context);
+            let (n, v) = self.gen_data_with_context(name, value, context)?;
             result.insert(n, v);
         }
 
-        Value::Object(result)
+        Ok(Value::Object(result))
     }
 
     /// 'float1|.1-10': 10,
@@ -227,21 +317,26 @@ impl Generator {
         }
     }
 
-    fn string(&self, rule: &Rule, data: &String, _context: &GeneratorContext) -> Value {
-        let mut result = data.clone();
+    fn string(
+        &self,
+        rule: &Rule,
+        data: &str,
+        _context: &GeneratorContext,
+    ) -> Result<Value, String> {
+        self.check_string(data)?;
+        let mut result = data.to_string();
 
-        // 1 function name  2 function parameters
-        let re_fn = Regex::new(r"@([^@#%&()\?\s]+)(?:\((.*?)\))?").unwrap();
-        let fn_capture = re_fn.captures(data);
+        let fn_capture = RE_FN.captures(data);
 
         if let Some(cap) = fn_capture {
             let fn_name = cap.get(1).map(|m| m.as_str()).unwrap();
 
+            // the parameters are checked above
             let mut range: Vec<usize> = Vec::new();
             if let Some(p) = cap.get(2).map(|m| m.as_str()) {
                 range = p
                     .split(",")
-                    .map(|item| item.trim().parse::<usize>().unwrap())
+                    .filter_map(|item| item.trim().parse::<usize>().ok())
                     .collect();
             }
 
@@ -258,19 +353,22 @@ impl Generator {
         if let Some(count) = rule.count {
             // it already has one value in result
             // just add count-1 to make sure the result has the item of count
-            for _ in 0..count - 1 {
+            for _ in 1..count {
                 result.push_str(data);
             }
+            if count == 0 {
+                result.clear();
+            }
         }
 
-        Value::String(result)
+        Ok(Value::String(result))
     }
 
     fn bool(&self, rule: &Rule, data: &bool, _context: &GeneratorContext) -> Value {
         if rule.is_rule {
             Value::Bool(utils::bool())
         } else {
-            Value::Bool(data.clone())
+            Value::Bool(*data)
         }
     }
 }
@@ -1,5 +1,8 @@
-use uuid::Uuid;
+use crate::template::utils;
+use uuid::Builder;
 
 pub fn uuid(_min: usize, _max: usize) -> String {
-    Uuid::new_v4().to_string()
+    Builder::from_random_bytes(utils::bytes())
+        .into_uuid()
+        .to_string()
 }
@@ -13,11 +13,55 @@ mod text;
 mod utils;
 mod value;
 
-pub fn gen_data<'a>(name: &str, value: &Value) -> (String, Value) {
+/// generate data from the name and the template, the invalid template is an error
+pub fn gen_data(name: &str, value: &Value) -> Result<(String, Value), String> {
     let gen = Generator::new();
     gen.gen_data(name, value)
 }
 
+/// generate data with the seed, the same seed always generates the same data
+pub fn gen_data_with_seed(
+    name: &str,
+    value: &Value,
+    seed: Option<u64>,
+) -> Result<(String, Value), String> {
+    utils::with_seed(seed, &parse_name(name), || gen_data(name, value))
+}
+
+/// get the real name from the name with rule, such as `name|1-10`,
+/// it is the part before the last `|` and the rule is not parsed
+pub fn parse_name(name: &str) -> String {
+    match name.rsplit_once('|') {
+        Some((name, _)) if !name.is_empty() => name.to_string(),
+        _ => name.to_string(),
+    }
+}
+
+/// get the step of the increment rule, such as `id|+1`
+pub fn parse_step(name: &str) -> Option<usize> {
+    let rule = Generator::new().parse_rule(name).ok()?;
+    if rule.is_step {
+        return Some(rule.step.unwrap_or(1));
+    }
+
+    None
+}
+
+/// generate a random uuid
+pub fn uuid() -> String {
+    id::uuid(0, 0)
+}
+
+/// check the rule of the name, such as `name|1-10`
+pub fn check_rule(name: &str) -> Result<(), String> {
+    Generator::new().check_rule(name)
+}
+
+/// check the function call in the string, such as `@word(1, 10)`
+pub fn check_string(value: &str) -> Result<(), String> {
+    Generator::new().check_string(value)
+}
+
 pub fn call(name: &str, params: (usize, usize)) -> String {
     let map = get_fn_mapping();
 
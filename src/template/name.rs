@@ -1,4 +1,4 @@
-use rand::Rng;
+use crate::template::utils;
 
 pub fn first() -> String {
     let first_male_names = [
@@ -73,8 +73,7 @@ pub fn first() -> String {
     ];
     let first_names = [first_male_names, first_female_names].concat();
 
-    let mut rng = rand::thread_rng();
-    let idx = rng.gen_range(1..first_names.len());
+    let idx = utils::random(1, first_names.len() - 1);
     first_names[idx].to_string()
 }
 
@@ -114,8 +113,7 @@ pub fn last() -> String {
         "Allen",
     ];
 
-    let mut rng = rand::thread_rng();
-    let idx = rng.gen_range(1..last_names.len());
+    let idx = utils::random(1, last_names.len() - 1);
     last_names[idx].to_string()
 }
 
@@ -27,5 +27,5 @@ pub fn paragraph(min: usize, max: usize) -> String {
     for _ in 0..len {
         ret.push(sentence(12, 18))
     }
-    return ret.join(" ");
+    ret.join(" ")
 }
@@ -1,13 +1,42 @@
 use rand::prelude::*;
 use serde_json::Value;
+use std::cell::RefCell;
 use std::collections::HashMap;
 
+thread_local! {
+    // all of the random data is generated from this rng
+    static RNG: RefCell<StdRng> = RefCell::new(StdRng::from_entropy());
+}
+
+/// run the function with the rng seeded by the seed and name,
+/// so the data of the same name is always the same with the same seed
+pub fn with_seed<T>(seed: Option<u64>, name: &str, f: impl FnOnce() -> T) -> T {
+    let seed = match seed {
+        Some(seed) => seed,
+        None => return f(),
+    };
+
+    // fnv-1a, the std hasher is not stable between the releases
+    let hash = name.bytes().fold(0xcbf29ce484222325_u64, |hash, b| {
+        (hash ^ b as u64).wrapping_mul(0x100000001b3)
+    });
+
+    RNG.with(|rng| *rng.borrow_mut() = StdRng::seed_from_u64(seed ^ hash));
+    let ret = f();
+    RNG.with(|rng| *rng.borrow_mut() = StdRng::from_entropy());
+
+    ret
+}
+
 pub fn random(min: usize, max: usize) -> usize {
-    let mut rng = rand::thread_rng();
-    rng.gen_range(min..=max)
+    RNG.with(|rng| rng.borrow_mut().gen_range(min..=max))
+}
+
+pub fn bytes() -> [u8; 16] {
+    RNG.with(|rng| rng.borrow_mut().gen())
 }
 
-pub fn pick(arr: &Vec<Value>) -> &Value {
+pub fn pick(arr: &[Value]) -> &Value {
     let index = random(0, arr.len() - 1);
 
     &arr[index]
@@ -43,7 +72,7 @@ pub fn float(min: usize, max: usize, dmin: usize, dmax: usize) -> f64 {
     let count = random(min, max);
 
     ret.push_str(&count.to_string());
-    ret.push_str(".");
+    ret.push('.');
     let dcount = random(dmin, dmax);
     for _ in 0..dcount {
         ret.push(char("number"))
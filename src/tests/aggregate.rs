@@ -0,0 +1,94 @@
+use crate::Database;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+fn orders() -> Database {
+    let db = Database::new();
+    db.load(&json!({
+        "data": {
+            "orders": [
+                { "id": 1, "status": "paid", "price": 10, "tags": ["a", "b"] },
+                { "id": 2, "status": "paid", "price": 2.5, "tags": ["b"] },
+                { "id": 3, "status": "open", "price": 5 },
+                { "id": 4, "status": "open", "price": 20, "tags": ["b"] },
+                { "id": 5, "price": 1 }
+            ]
+        }
+    }))
+    .unwrap();
+    db
+}
+
+fn query(db: &Database, query: &[(&str, &str)]) -> Value {
+    let path = HashMap::from([("data".to_string(), "orders".to_string())]);
+    let query: Vec<(String, String)> = query
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    db.query_data(&path, &query).unwrap().0
+}
+
+#[test]
+fn aggregate_count() {
+    let db = orders();
+    assert_eq!(query(&db, &[("_count", "")]), json!({ "count": 5 }));
+    assert_eq!(
+        query(&db, &[("_count", "true"), ("status", "paid")]),
+        json!({ "count": 2 })
+    );
+}
+
+#[test]
+fn aggregate_metrics() {
+    let db = orders();
+    let value = query(
+        &db,
+        &[("_sum", "price"), ("_max", "price"), ("_min", "status")],
+    );
+    assert_eq!(
+        value,
+        json!({
+            "count": 5,
+            "sum": { "price": 38.5 },
+            "max": { "price": 20 },
+            "min": { "status": "open" }
+        })
+    );
+
+    let value = query(
+        &db,
+        &[("_sum", "price"), ("_avg", "price"), ("price_gte", "5")],
+    );
+    assert_eq!(value["sum"]["price"], 35);
+    assert_eq!(value["avg"]["price"], json!(35.0 / 3.0));
+
+    let value = query(&db, &[("_avg", "price"), ("id", "9")]);
+    assert_eq!(value, json!({ "count": 0, "avg": { "price": null } }));
+}
+
+#[test]
+fn aggregate_group_by() {
+    let db = orders();
+    let value = query(&db, &[("_group_by", "status"), ("_sum", "price")]);
+    assert_eq!(
+        value,
+        json!([
+            { "status": null, "count": 1, "sum": { "price": 1 } },
+            { "status": "open", "count": 2, "sum": { "price": 25 } },
+            { "status": "paid", "count": 2, "sum": { "price": 12.5 } }
+        ])
+    );
+}
+
+#[test]
+fn aggregate_facet() {
+    let db = orders();
+    let value = query(&db, &[("_facet", "status,tags")]);
+    assert_eq!(
+        value["facets"],
+        json!({
+            "status": [{ "value": "open", "count": 2 }, { "value": "paid", "count": 2 }],
+            "tags": [{ "value": "b", "count": 3 }, { "value": "a", "count": 1 }]
+        })
+    );
+}
@@ -0,0 +1,262 @@
+use crate::{app, models::DataConfig, Database};
+use axum::{
+    body::Body,
+    http::{header, HeaderMap, Method, Request, StatusCode},
+    Extension, Router,
+};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use tower::ServiceExt;
+
+fn config(routing: serde_json::Value) -> DataConfig {
+    DataConfig::new(&json!({ "routing": routing })).unwrap()
+}
+
+#[test]
+fn app_build() {
+    let routing = json!({ "/a/:id": { "to": "/api/a/:id" }, "/b": { "to": "/api/b" } });
+    assert!(app::build(&config(routing)).is_ok());
+}
+
+#[test]
+fn app_build_conflict() {
+    // the conflicting routes are error instead of panic
+    let routing = json!({ "/x/:a": { "to": "/api/a" }, "/x/:b/c": { "to": "/api/a" } });
+    assert!(app::build(&config(routing)).is_err());
+
+    let routing = json!({ "/api/:x": { "to": "/api/a" } });
+    assert!(app::build(&config(routing)).is_err());
+
+    let routing = json!({ "/x/:a": { "to": "/api/a" }, "/y/:b/c": { "to": "/api/a" } });
+    assert!(app::build(&config(routing)).is_ok());
+}
+
+#[test]
+fn app_stage_keeps_data() {
+    let dir = std::env::temp_dir().join(format!("mock-server-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("db.json");
+    std::fs::write(&path, r#"{ "data": { "b": [{ "id": 1 }] } }"#).unwrap();
+
+    let db = Database::new();
+    db.load(&json!({ "data": { "a": [{ "id": 1 }] } })).unwrap();
+    let staged = db.stage(path.to_str().unwrap()).unwrap();
+
+    // the staged config is not applied yet
+    let data = HashMap::from([("data".to_string(), "a".to_string())]);
+    assert!(db.query_data(&data, &[]).is_ok());
+
+    db.apply(staged);
+    let data = HashMap::from([("data".to_string(), "b".to_string())]);
+    assert!(db.query_data(&data, &[]).is_ok());
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+fn router(json: Value) -> Router {
+    let db = Database::new();
+    db.load(&json).unwrap();
+    app::build(&db.get_config()).unwrap().layer(Extension(db))
+}
+
+async fn send(
+    router: &Router,
+    method: Method,
+    uri: &str,
+    content_type: &str,
+    body: &str,
+) -> (StatusCode, HeaderMap, String) {
+    let req = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header(header::CONTENT_TYPE, content_type)
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    let res = router.clone().oneshot(req).await.unwrap();
+    let (status, headers) = (res.status(), res.headers().clone());
+    let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+
+    (status, headers, String::from_utf8(body.to_vec()).unwrap())
+}
+
+#[tokio::test]
+async fn app_patch() {
+    let router = router(json!({
+        "config": { "routing": { "/items/:id": { "to": "/api/items/:id" } } },
+        "data": { "items": [{ "id": 1, "a": 1, "b": 2 }] }
+    }));
+
+    // the content type selects the json patch over the merge patch
+    let patch = r#"[{ "op": "replace", "path": "/a", "value": 3 }]"#;
+    let (_, _, body) = send(
+        &router,
+        Method::PATCH,
+        "/api/items/1",
+        "application/json-patch+json",
+        patch,
+    )
+    .await;
+    assert_eq!(
+        serde_json::from_str::<Value>(&body).unwrap(),
+        json!({ "id": 1, "a": 3, "b": 2 })
+    );
+
+    let patch = r#"{ "b": null }"#;
+    let (_, _, body) = send(
+        &router,
+        Method::PATCH,
+        "/items/1",
+        "application/merge-patch+json",
+        patch,
+    )
+    .await;
+    assert_eq!(
+        serde_json::from_str::<Value>(&body).unwrap(),
+        json!({ "id": 1, "a": 3 })
+    );
+
+    // the body-less patch never replaces the record
+    let (_, _, body) = send(&router, Method::PATCH, "/items/1", "application/json", "").await;
+    assert_eq!(body, "the merge patch must be object type");
+    let (_, _, body) = send(&router, Method::GET, "/api/items/1", "application/json", "").await;
+    assert_eq!(
+        serde_json::from_str::<Value>(&body).unwrap(),
+        json!({ "id": 1, "a": 3 })
+    );
+}
+
+#[tokio::test]
+async fn app_key_conflict() {
+    let router = router(json!({
+        "config": { "routing": { "/items/:id": { "to": "/api/items/:id" } } },
+        "data": { "items": [{ "id": 1 }, { "id": 2 }] }
+    }));
+
+    for uri in ["/api/items/2", "/items/2"] {
+        let (status, _, _) = send(
+            &router,
+            Method::PATCH,
+            uri,
+            "application/json",
+            r#"{ "id": 1 }"#,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CONFLICT, "{}", uri);
+
+        let (status, _, _) = send(
+            &router,
+            Method::PUT,
+            uri,
+            "application/json",
+            r#"{ "id": 1 }"#,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CONFLICT, "{}", uri);
+    }
+
+    let (status, _, _) = send(
+        &router,
+        Method::PATCH,
+        "/api/items/2",
+        "application/json",
+        r#"{ "id": 3 }"#,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn app_create() {
+    let router = router(json!({
+        "config": { "routing": {
+            "/users": { "to": "/api/users" },
+            "/posts/:pid/comments": { "to": "/api/posts/:pid/comments" }
+        } },
+        "data": { "users|2": [{ "id|+1": 1 }], "posts|2": [{ "id|+1": 1 }], "comments": [] }
+    }));
+
+    let (status, headers, body) = send(
+        &router,
+        Method::POST,
+        "/api/users",
+        "application/json",
+        r#"{ "name": "a" }"#,
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(headers[header::LOCATION], "/api/users/3");
+    assert_eq!(
+        serde_json::from_str::<Value>(&body).unwrap(),
+        json!({ "id": 3, "name": "a" })
+    );
+
+    let (status, _, _) = send(
+        &router,
+        Method::POST,
+        "/api/users",
+        "application/json",
+        r#"{ "id": 1 }"#,
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    // the routing target
+    let (status, headers, _) =
+        send(&router, Method::POST, "/users", "application/json", "{}").await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(headers[header::LOCATION], "/api/users/4");
+
+    let (status, _, _) = send(
+        &router,
+        Method::POST,
+        "/users",
+        "application/json",
+        r#"{ "id": 4 }"#,
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let (status, headers, body) = send(
+        &router,
+        Method::POST,
+        "/posts/2/comments",
+        "application/json",
+        "{}",
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(headers[header::LOCATION], "/api/posts/2/comments/1");
+    assert_eq!(
+        serde_json::from_str::<Value>(&body).unwrap(),
+        json!({ "id": 1, "postId": 2 })
+    );
+}
+
+#[tokio::test]
+async fn app_page_invalid() {
+    let router = router(json!({
+        "config": { "routing": { "/users": { "to": "/api/users" } } },
+        "data": { "users|5": [{ "id|+1": 1 }] }
+    }));
+
+    for uri in [
+        "/api/users?_page=0",
+        "/api/users?_page=a",
+        "/api/users?_limit=0",
+        "/api/users?_cursor=abc",
+        "/users?_page=0",
+    ] {
+        let (status, _, _) = send(&router, Method::GET, uri, "application/json", "").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", uri);
+    }
+
+    let (status, headers, _) = send(
+        &router,
+        Method::GET,
+        "/api/users?_page=2&_size=2",
+        "application/json",
+        "",
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(headers["x-total-count"], "5");
+}
@@ -5,20 +5,21 @@ use serde_json::Value;
 /// name|1: [ "a", "b", "c" ]
 #[test]
 fn array_pick() {
-    let (name, value) = template::gen_data("name|1", &json!(["a", "b", "c"]));
+    let (name, value) = template::gen_data("name|1", &json!(["a", "b", "c"])).unwrap();
 
     assert_eq!(name, "name");
     if let Value::String(v) = value {
         return assert!(["a".to_string(), "b".to_string(), "c".to_string()].contains(&v));
     }
 
-    assert!(false);
+    panic!("unexpected value");
 }
 
 /// name|3: [ { "id|+1" ["a", "b", "c"] }]
 #[test]
 fn array_order() {
-    let (name, value) = template::gen_data("name|3", &json!([ { "id|+1": ["a", "b", "c"] }]));
+    let (name, value) =
+        template::gen_data("name|3", &json!([ { "id|+1": ["a", "b", "c"] }])).unwrap();
 
     assert_eq!(name, "name");
     println!("generated: {}", value);
@@ -42,7 +43,7 @@ fn array_order() {
 /// name|3: [ { "id|+1" 1 }]
 #[test]
 fn array_increment() {
-    let (name, value) = template::gen_data("name|3", &json!([ { "id|+1": 1 }]));
+    let (name, value) = template::gen_data("name|3", &json!([ { "id|+1": 1 }])).unwrap();
 
     assert_eq!(name, "name");
     println!("generated: {}", value);
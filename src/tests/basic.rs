@@ -5,7 +5,7 @@ use serde_json::Value;
 /// name: "abc"
 #[test]
 fn none_template_str() {
-    let (name, value) = template::gen_data("name", &json!("abc"));
+    let (name, value) = template::gen_data("name", &json!("abc")).unwrap();
 
     assert_eq!(name, "name");
     assert!(matches!(value, Value::String(v) if v == "abc" ));
@@ -14,7 +14,7 @@ fn none_template_str() {
 /// name:  123
 #[test]
 fn none_template_number() {
-    let (name, value) = template::gen_data("name", &json!(123));
+    let (name, value) = template::gen_data("name", &json!(123)).unwrap();
 
     assert_eq!(name, "name");
     assert!(matches!(value, Value::Number(v) if v.as_i64().unwrap() == 123  ));
@@ -23,25 +23,25 @@ fn none_template_number() {
 /// name:  {}
 #[test]
 fn none_template_object() {
-    let (name, value) = template::gen_data("name", &json!({}));
+    let (name, value) = template::gen_data("name", &json!({})).unwrap();
 
     assert_eq!(name, "name");
-    assert!(matches!(value, Value::Object(v) if v.len() == 0  ));
+    assert!(matches!(value, Value::Object(v) if v.is_empty()));
 }
 
 /// name:  []
 #[test]
 fn none_template_arr() {
-    let (name, value) = template::gen_data("name", &json!([]));
+    let (name, value) = template::gen_data("name", &json!([])).unwrap();
 
     assert_eq!(name, "name");
-    assert!(matches!(value, Value::Array(v) if v.len() == 0  ));
+    assert!(matches!(value, Value::Array(v) if v.is_empty()));
 }
 
 /// name|min-max: "abc"
 #[test]
 fn random_str_count() {
-    let (name, value) = template::gen_data("name|1-20", &json!("abc"));
+    let (name, value) = template::gen_data("name|1-20", &json!("abc")).unwrap();
 
     assert_eq!(name, "name");
     assert!(matches!(value, Value::String(v) if v.len() >= 3 && v.len() <= 20*3 ));
@@ -50,7 +50,7 @@ fn random_str_count() {
 /// name|3: "a"
 #[test]
 fn fixed_str_count() {
-    let (name, value) = template::gen_data("name|3", &json!("a"));
+    let (name, value) = template::gen_data("name|3", &json!("a")).unwrap();
 
     assert_eq!(name, "name");
     assert!(matches!(value, Value::String(v) if v.len()==3 ));
@@ -59,16 +59,16 @@ fn fixed_str_count() {
 /// name|min-max: [{}]
 #[test]
 fn random_count_array() {
-    let (name, value) = template::gen_data("name|1-20", &json!(["abc"]));
+    let (name, value) = template::gen_data("name|1-20", &json!(["abc"])).unwrap();
 
     assert_eq!(name, "name");
-    assert!(matches!(value, Value::Array(v) if v.len() >= 1 && v.len() <= 20 ));
+    assert!(matches!(value, Value::Array(v) if (1..=20).contains(&v.len())));
 }
 
 /// name|count: [{}]
 #[test]
 fn fixed_count_array() {
-    let (name, value) = template::gen_data("name|3", &json!(["a"]));
+    let (name, value) = template::gen_data("name|3", &json!(["a"])).unwrap();
 
     assert_eq!(name, "name");
     assert!(matches!(value, Value::Array(v) if v.len()==3 ));
@@ -77,7 +77,7 @@ fn fixed_count_array() {
 /// name|+1: [{},{},{}]
 #[test]
 fn serial_count_str() {
-    let (name, value) = template::gen_data("name|+1", &json!(["a", "b", "c"]));
+    let (name, value) = template::gen_data("name|+1", &json!(["a", "b", "c"])).unwrap();
 
     assert_eq!(name, "name");
     assert!(matches!(value, Value::String(v) if v == "a" ));
@@ -86,7 +86,7 @@ fn serial_count_str() {
 /// name|1-100: 1
 #[test]
 fn random_number_u64() {
-    let (name, value) = template::gen_data("name|1-100", &json!(1));
+    let (name, value) = template::gen_data("name|1-100", &json!(1)).unwrap();
 
     assert_eq!(name, "name");
     assert!(
@@ -97,7 +97,7 @@ fn random_number_u64() {
 /// name|1-100.3-10: 10.123
 #[test]
 fn random_decimal_number_f64() {
-    let (name, value) = template::gen_data("name|1-100.3-10", &json!(10.123));
+    let (name, value) = template::gen_data("name|1-100.3-10", &json!(10.123)).unwrap();
 
     assert_eq!(name, "name");
     if let Value::Number(v) = value {
@@ -107,17 +107,17 @@ fn random_decimal_number_f64() {
         let integer: i64 = arr[0].parse().unwrap();
         let decimal_str = arr[1];
 
-        assert!(integer >= 1 && integer <= 100);
+        assert!((1..=100).contains(&integer));
         assert!(decimal_str.len() >= 3 && decimal_str.len() <= 10);
     } else {
-        assert!(false)
+        panic!("unexpected value")
     }
 }
 
 /// name|1-100.123: 10.123
 #[test]
 fn min_decimal_number_f64() {
-    let (name, value) = template::gen_data("name|1-100.4", &json!(10.123));
+    let (name, value) = template::gen_data("name|1-100.4", &json!(10.123)).unwrap();
 
     println!("{}", value);
     assert_eq!(name, "name");
@@ -128,17 +128,17 @@ fn min_decimal_number_f64() {
         let integer: i64 = arr[0].parse().unwrap();
         let decimal_str = arr[1];
 
-        assert!(integer >= 1 && integer <= 100);
+        assert!((1..=100).contains(&integer));
         assert!(decimal_str.len() == 4);
     } else {
-        assert!(false)
+        panic!("unexpected value")
     }
 }
 
 /// name|1-100.123: 10.123
 #[test]
 fn dot_decimal_number_f64() {
-    let (name, value) = template::gen_data("name|.3-10", &json!(0.123));
+    let (name, value) = template::gen_data("name|.3-10", &json!(0.123)).unwrap();
 
     assert_eq!(name, "name");
     if let Value::Number(v) = value {
@@ -150,6 +150,15 @@ fn dot_decimal_number_f64() {
         assert!(integer == 0);
         assert!(decimal_str.len() >= 3 && decimal_str.len() <= 10);
     } else {
-        assert!(false)
+        panic!("unexpected value")
     }
 }
+
+#[test]
+fn invalid_rule() {
+    assert!(template::gen_data("name|3-1", &json!(1)).is_err());
+    assert!(template::gen_data("name|99999999999999999999999", &json!(1)).is_err());
+    assert!(template::gen_data("name", &json!({ "a|2": [] })).is_err());
+    assert!(template::gen_data("name", &json!("@word(a)")).is_err());
+    assert_eq!(template::gen_data("name|0", &json!("a")).unwrap().1, "");
+}
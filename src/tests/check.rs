@@ -0,0 +1,175 @@
+use crate::check::check;
+use serde_json::json;
+
+fn paths(json: serde_json::Value) -> Vec<String> {
+    check(&json).into_iter().map(|p| p.path).collect()
+}
+
+#[test]
+fn check_valid() {
+    let problems = check(&json!({
+        "config": {
+            "routing": {
+                "/a/:id": { "to": "/api/items/:id", "rules": [{ "key": "id", "match": "\\d+", "message": "id" }] },
+                "/b": { "to": "/api/:data" },
+                "/c/:id": { "to": "/file/:id" }
+            },
+            "wrapping": { "ok": { "nested": { "data": "$data" } }, "pagination": { "rows": "$items" } }
+        },
+        "data": { "items|1-10": [{ "id|+1": 1, "name": "@word(1, 5)", "f|1-10.1-3": 1.5 }] }
+    }));
+
+    assert_eq!(problems, vec![]);
+}
+
+#[test]
+fn check_routing() {
+    let problems = paths(json!({
+        "config": {
+            "routing": {
+                "/a": { "to": "/api/nope" },
+                "/b": { "to": "/other" },
+                "/c/:id": { "to": "/api/items/:id", "rules": [{ "key": "id", "match": "(", "message": "id" }] },
+                "/d": { "to": "/file/nope" }
+            }
+        },
+        "data": { "items": [] }
+    }));
+
+    assert_eq!(
+        problems,
+        vec![
+            "$.config.routing['/a'].to",
+            "$.config.routing['/b'].to",
+            "$.config.routing['/c/:id'].rules[0].match",
+            "$.config.routing['/d'].to"
+        ]
+    );
+}
+
+#[test]
+fn check_template() {
+    let problems = paths(json!({
+        "data": {
+            "a|x-b": [{ "n": "@nope" }],
+            "b|10-1": 1,
+            "c": { "d": "@word(1, a)" }
+        }
+    }));
+
+    assert_eq!(
+        problems,
+        vec![
+            "$.data['a|x-b']",
+            "$.data['a|x-b'][0].n",
+            "$.data['b|10-1']",
+            "$.data.c.d"
+        ]
+    );
+}
+
+#[test]
+fn check_wrapping() {
+    let problems = paths(json!({
+        "config": { "wrapping": { "ok": { "code": 200 }, "err": { "msg": "$msg" } } }
+    }));
+
+    assert_eq!(problems, vec!["$.config.wrapping.ok"]);
+}
+
+#[test]
+fn check_page_headers() {
+    assert!(paths(json!({ "config": { "page_headers": "headers" } })).is_empty());
+    assert_eq!(
+        paths(json!({ "config": { "page_headers": "none" } })),
+        vec!["$.config.page_headers"]
+    );
+}
+
+#[test]
+fn check_relations() {
+    let problems = paths(json!({
+        "config": { "relations": { "comments": { "postId": "posts", "userId": "users" }, "x": {} } },
+        "data": { "comments": [], "posts": [] }
+    }));
+
+    assert_eq!(
+        problems,
+        vec!["$.config.relations.comments.userId", "$.config.relations.x"]
+    );
+}
+
+#[test]
+fn check_keys() {
+    let problems = paths(json!({
+        "config": { "keys": { "users": "uuid", "stocks": ["sku", "store"], "x": "id", "bad": [], "n": 1 } },
+        "data": { "users": [], "stocks": [], "bad": [], "n": [] }
+    }));
+
+    assert_eq!(
+        problems,
+        vec!["$.config.keys.x", "$.config.keys.bad", "$.config.keys.n"]
+    );
+}
+
+#[test]
+fn check_nested_target() {
+    let problems = paths(json!({
+        "config": {
+            "routing": {
+                "/a/:id": { "to": "/api/posts/:id/comments" },
+                "/b/:id/:cid": { "to": "/api/posts/:id/nope/:cid" }
+            }
+        },
+        "data": { "posts": [], "comments": [] }
+    }));
+
+    assert_eq!(problems, vec!["$.config.routing['/b/:id/:cid'].to"]);
+}
+
+#[test]
+fn check_select() {
+    let problems = paths(json!({
+        "config": {
+            "routing": {
+                "/a": { "to": "/api/a", "select": "[].name" },
+                "/b": { "to": "/api/a", "select": "$..name" },
+                "/c": { "to": "/api/a", "select": "[." }
+            }
+        },
+        "data": { "a": [] }
+    }));
+
+    assert_eq!(problems, vec!["$.config.routing['/c'].select"]);
+}
+
+#[test]
+fn check_records() {
+    let problems = paths(json!({
+        "config": { "routing": { "/a": { "to": "/api/a" } }, "keys": { "a": "sku" } },
+        "records": { "a": [{ "sku": "x" }] }
+    }));
+    assert!(problems.is_empty());
+
+    assert_eq!(paths(json!({ "records": [] })), vec!["$.records"]);
+}
+
+#[test]
+fn check_invalid_rule_key() {
+    let dir = std::env::temp_dir().join(format!("mock-server-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("db.json");
+    let json = json!({
+        "config": { "seed": 1 },
+        "data": { "v|3-1": [{ "id": 1 }], "x|99999999999999999999999": 1 }
+    });
+    std::fs::write(&file, json.to_string()).unwrap();
+
+    // the invalid rules are reported instead of panic
+    let loaded = crate::loader::load(file.to_str().unwrap()).unwrap();
+    assert_eq!(
+        paths(loaded.json),
+        vec!["$.data['v|3-1']", "$.data['x|99999999999999999999999']"]
+    );
+    std::fs::remove_dir_all(dir).unwrap();
+}
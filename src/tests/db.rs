@@ -0,0 +1,375 @@
+use crate::{persist::Persister, Database};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+fn path(data: &str) -> HashMap<String, String> {
+    HashMap::from([("data".to_string(), data.to_string())])
+}
+
+#[test]
+fn db_load() {
+    let db = Database::new();
+    db.load(&json!({ "data": { "items|3": [{ "id|+1": 1 }] } }))
+        .unwrap();
+
+    let value = db.query_data(&path("items"), &[]).unwrap().0;
+    assert_eq!(value.as_array().unwrap().len(), 3);
+}
+
+#[test]
+fn db_load_keep_old_data_with_error() {
+    let db = Database::new();
+    db.load(&json!({ "data": { "items|3": [{ "id|+1": 1 }] } }))
+        .unwrap();
+
+    let ret = db.load(&json!({
+        "config": { "routing": { "no_slash": { "to": "/api/items" } } },
+        "data": { "others": [] }
+    }));
+    assert!(ret.is_err());
+
+    let value = db.query_data(&path("items"), &[]).unwrap().0;
+    assert_eq!(value.as_array().unwrap().len(), 3);
+    assert!(db.query_data(&path("others"), &[]).is_err());
+}
+
+#[test]
+fn db_restore_persisted() {
+    let file = std::env::temp_dir().join(format!("mock-server-{}.json", uuid::Uuid::new_v4()));
+    let persister = Persister::new(file.to_str().unwrap());
+
+    let db = Database::new().with_persister(persister.clone());
+    db.load(&json!({ "data": { "items|3": [{ "id|+1": 1 }], "others": [] } }))
+        .unwrap();
+    assert!(!db.restore_persisted().unwrap());
+
+    persister
+        .save(&HashMap::from([(
+            "items".to_string(),
+            json!([{ "id": 100 }]),
+        )]))
+        .unwrap();
+    assert!(db.restore_persisted().unwrap());
+    std::fs::remove_file(file).unwrap();
+
+    let value = db.query_data(&path("items"), &[]).unwrap().0;
+    assert_eq!(value, json!([{ "id": 100 }]));
+    assert!(db.query_data(&path("others"), &[]).is_ok());
+}
+
+#[test]
+fn db_snapshot_and_reset() {
+    let db = Database::new();
+    db.load(&json!({ "data": { "items|3": [{ "id|+1": 1 }], "others": [1] } }))
+        .unwrap();
+
+    db.take_snapshot("s1").unwrap();
+    db.restore(HashMap::from([
+        ("items".to_string(), json!([])),
+        ("others".to_string(), json!([])),
+    ]));
+
+    db.reset(Some("items")).unwrap();
+    let value = db.query_data(&path("items"), &[]).unwrap().0;
+    assert_eq!(value.as_array().unwrap().len(), 3);
+    let value = db.query_data(&path("others"), &[]).unwrap().0;
+    assert_eq!(value, json!([]));
+
+    db.restore_snapshot("s1").unwrap();
+    let value = db.query_data(&path("others"), &[]).unwrap().0;
+    assert_eq!(value, json!([1]));
+
+    assert!(db.reset(Some("not_exist")).is_err());
+    assert!(db.restore_snapshot("not_exist").is_err());
+    assert_eq!(db.export()["records"]["others"], json!([1]));
+}
+
+#[test]
+fn db_seed() {
+    let json = json!({
+        "config": { "seed": 42 },
+        "data": {
+            "items|5-20": [{ "id": "@uuid", "name": "@name", "age|1-100": 1, "pick|1": ["a", "b", "c"] }],
+            "others": "@sentence"
+        }
+    });
+
+    let db1 = Database::new();
+    db1.load(&json).unwrap();
+    let db2 = Database::new();
+    db2.load(&json).unwrap();
+    assert_eq!(db1.snapshot(), db2.snapshot());
+
+    // reset the collection to the same data
+    let items = db1.snapshot()["items"].clone();
+    db1.restore(HashMap::from([("items".to_string(), json!([]))]));
+    db1.reset(Some("items")).unwrap();
+    assert_eq!(db1.snapshot()["items"], items);
+
+    // the seed in args overrides the one in config
+    let db3 = Database::new().with_seed(Some(7));
+    db3.load(&json).unwrap();
+    assert_ne!(db1.snapshot()["items"], db3.snapshot()["items"]);
+}
+
+#[test]
+fn db_patch() {
+    use crate::db::Patch;
+
+    let mut db = Database::new();
+    db.load(&json!({ "data": { "items": [{ "id": 1, "a": 1, "b": { "c": 1, "d": 2 } }] } }))
+        .unwrap();
+    let path = HashMap::from([
+        ("data".to_string(), "items".to_string()),
+        ("id".to_string(), "1".to_string()),
+    ]);
+
+    let value = db
+        .patch_data(&path, Patch::Merge(json!({ "a": null, "b": { "c": 3 } })))
+        .unwrap();
+    assert_eq!(value, json!({ "id": 1, "b": { "c": 3, "d": 2 } }));
+
+    let patch = json!([
+        { "op": "add", "path": "/tags", "value": ["x"] },
+        { "op": "replace", "path": "/b/d", "value": 4 }
+    ]);
+    let value = db.patch_data(&path, Patch::Json(patch)).unwrap();
+    assert_eq!(
+        value,
+        json!({ "id": 1, "b": { "c": 3, "d": 4 }, "tags": ["x"] })
+    );
+
+    // the failed patch keeps the item unchanged
+    let patch = json!([
+        { "op": "remove", "path": "/b" },
+        { "op": "test", "path": "/id", "value": 2 }
+    ]);
+    assert!(db.patch_data(&path, Patch::Json(patch)).is_err());
+    assert!(db
+        .patch_data(&path, Patch::Json(json!({ "op": "nope" })))
+        .is_err());
+    assert!(db.patch_data(&path, Patch::Merge(Value::Null)).is_err());
+    assert!(db.patch_data(&path, Patch::Merge(json!([1]))).is_err());
+
+    let value = db.query_data(&path, &[]).unwrap().0;
+    assert_eq!(value[0]["b"], json!({ "c": 3, "d": 4 }));
+
+    let path = HashMap::from([
+        ("data".to_string(), "items".to_string()),
+        ("id".to_string(), "9".to_string()),
+    ]);
+    assert!(db.patch_data(&path, Patch::Merge(json!({}))).is_err());
+}
+
+#[test]
+fn db_patch_key_conflict() {
+    use crate::db::{CreateError, Patch};
+
+    let mut db = Database::new();
+    db.load(&json!({ "data": { "items": [{ "id": 1 }, { "id": 2 }] } }))
+        .unwrap();
+    let path = HashMap::from([
+        ("data".to_string(), "items".to_string()),
+        ("id".to_string(), "2".to_string()),
+    ]);
+
+    // the id of the other record is a conflict
+    let ret = db.patch_data(&path, Patch::Merge(json!({ "id": 1 })));
+    assert!(matches!(ret, Err(CreateError::Conflict(_))));
+    let patch = json!([{ "op": "replace", "path": "/id", "value": 1 }]);
+    let ret = db.patch_data(&path, Patch::Json(patch));
+    assert!(matches!(ret, Err(CreateError::Conflict(_))));
+    assert_eq!(db.snapshot()["items"], json!([{ "id": 1 }, { "id": 2 }]));
+
+    // the unused id or the same id is fine
+    db.patch_data(&path, Patch::Merge(json!({ "id": 2, "a": 1 })))
+        .unwrap();
+    db.patch_data(&path, Patch::Merge(json!({ "id": 3 })))
+        .unwrap();
+    assert_eq!(
+        db.snapshot()["items"],
+        json!([{ "id": 1 }, { "id": 3, "a": 1 }])
+    );
+}
+
+#[test]
+fn db_update_key_conflict() {
+    use crate::db::CreateError;
+
+    let mut db = Database::new();
+    db.load(&json!({ "data": { "items": [{ "id": 1 }, { "id": 2 }] } }))
+        .unwrap();
+    let path = HashMap::from([
+        ("data".to_string(), "items".to_string()),
+        ("id".to_string(), "2".to_string()),
+    ]);
+
+    let ret = db.update_data(&path, json!({ "id": 1, "a": 1 }));
+    assert!(matches!(ret, Err(CreateError::Conflict(_))));
+    assert_eq!(db.snapshot()["items"], json!([{ "id": 1 }, { "id": 2 }]));
+
+    db.update_data(&path, json!({ "id": 3 })).unwrap();
+    assert_eq!(db.snapshot()["items"], json!([{ "id": 1 }, { "id": 3 }]));
+}
+
+#[test]
+fn db_create() {
+    use crate::db::CreateError;
+
+    let mut db = Database::new();
+    db.load(&json!({ "data": {
+        "items|3": [{ "id|+2": 10 }],
+        "uuids|2": [{ "id": "@uuid" }],
+        "others": []
+    } }))
+    .unwrap();
+
+    // continue the `id|+2` sequence of the template, the ids are 2, 3, 4
+    let value = db.create_data(&path("items"), json!({ "a": 1 })).unwrap();
+    assert_eq!(value, json!({ "id": 5, "a": 1 }));
+
+    let value = db.create_data(&path("items"), json!({ "id": 10 })).unwrap();
+    assert_eq!(value["id"], 10);
+
+    let ret = db.create_data(&path("items"), json!({ "id": 5 }));
+    assert!(matches!(ret, Err(CreateError::Conflict(_))));
+    let ret = db.create_data(&path("items"), json!([1]));
+    assert!(matches!(ret, Err(CreateError::Invalid(_))));
+
+    let value = db.create_data(&path("uuids"), json!({})).unwrap();
+    assert_eq!(value["id"].as_str().unwrap().len(), 36);
+
+    let value = db.create_data(&path("others"), json!({})).unwrap();
+    assert_eq!(value["id"], 1);
+    let value = db.create_data(&path("others"), json!({})).unwrap();
+    assert_eq!(value["id"], 2);
+
+    let value = db.query_data(&path("items"), &[]).unwrap().0;
+    assert_eq!(value.as_array().unwrap().len(), 5);
+}
+
+#[test]
+fn db_primary_key() {
+    let mut db = Database::new();
+    db.load(&json!({
+        "config": {
+            "keys": { "skus": "sku", "stocks": ["sku", "store"] },
+            "relations": { "orders": { "skuId": "skus" } }
+        },
+        "data": {
+            "users": [{ "id": "a1" }, { "id": "2" }, { "id": 3 }],
+            "skus": [{ "sku": "s1", "name": "x" }],
+            "stocks": [{ "sku": "s1", "store": 1, "qty": 5 }, { "sku": "s1", "store": 2, "qty": 0 }],
+            "orders": [{ "id": 1, "skuId": "s1" }]
+        }
+    }))
+    .unwrap();
+    let item = |data: &str, id: &str| {
+        HashMap::from([
+            ("data".to_string(), data.to_string()),
+            ("id".to_string(), id.to_string()),
+        ])
+    };
+
+    // the string and number ids
+    assert_eq!(db.get_data(&item("users", "a1"), &[]).unwrap()["id"], "a1");
+    assert_eq!(db.get_data(&item("users", "2"), &[]).unwrap()["id"], "2");
+    assert_eq!(db.get_data(&item("users", "3"), &[]).unwrap()["id"], 3);
+    assert!(db.get_data(&item("users", "b1"), &[]).is_err());
+
+    // the custom and composite keys
+    let value = db.get_data(&item("skus", "s1"), &[]).unwrap();
+    assert_eq!(value["name"], "x");
+    let value = db.get_data(&item("stocks", "s1,2"), &[]).unwrap();
+    assert_eq!(value["qty"], 0);
+    assert!(db.get_data(&item("stocks", "s1"), &[]).is_err());
+
+    let value = db
+        .update_data(&item("skus", "s1"), json!({ "sku": "s1", "name": "y" }))
+        .unwrap();
+    assert_eq!(value["name"], "y");
+
+    let query = [("_expand".to_string(), "sku".to_string())];
+    let value = db.get_data(&item("orders", "1"), &query).unwrap();
+    assert_eq!(value["sku"]["name"], "y");
+
+    let ret = db.create_data(&path("stocks"), json!({ "sku": "s1", "store": 1 }));
+    assert!(ret.is_err());
+    let ret = db.create_data(&path("stocks"), json!({ "sku": "s2" }));
+    assert!(ret.is_err());
+    db.create_data(&path("stocks"), json!({ "sku": "s2", "store": 1 }))
+        .unwrap();
+
+    db.delete_data(&item("stocks", "s1,1")).unwrap();
+    assert!(db.delete_data(&item("stocks", "s1,1")).is_err());
+    // the unknown collection is not found instead of panic
+    assert!(db.get_data(&item("nope", "1"), &[]).is_err());
+    assert!(db.update_data(&item("nope", "1"), json!({})).is_err());
+    assert!(db.delete_data(&item("nope", "1")).is_err());
+    assert!(db.get_data(&item("users", "a1"), &[]).is_ok());
+
+    let value = db.query_data(&path("stocks"), &[]).unwrap().0;
+    assert_eq!(
+        value,
+        json!([{ "sku": "s1", "store": 2, "qty": 0 }, { "sku": "s2", "store": 1 }])
+    );
+}
+
+#[tokio::test]
+async fn db_persist_only_changed() {
+    let file = std::env::temp_dir().join(format!("mock-server-{}.json", uuid::Uuid::new_v4()));
+    let persister = Persister::new(file.to_str().unwrap());
+
+    let mut db = Database::new().with_persister(persister.clone());
+    tokio::spawn(persister.run(db.clone()));
+    db.load(&json!({ "data": { "items": [] } })).unwrap();
+    db.restore_persisted().unwrap();
+
+    // loading the config is not a change
+    tokio::time::sleep(std::time::Duration::from_millis(700)).await;
+    assert!(!file.exists());
+
+    db.create_data(&path("items"), json!({})).unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(700)).await;
+    assert!(file.exists());
+    std::fs::remove_file(file).unwrap();
+}
+
+#[tokio::test]
+async fn db_persist_flush() {
+    let file = std::env::temp_dir().join(format!("mock-server-{}.json", uuid::Uuid::new_v4()));
+    let persister = Persister::new(file.to_str().unwrap());
+
+    let mut db = Database::new().with_persister(persister.clone());
+    db.load(&json!({ "data": { "items": [] } })).unwrap();
+    db.restore_persisted().unwrap();
+    assert_eq!(persister.flush(&db).await, Ok(false));
+
+    // the pending change is saved without waiting for the debounce
+    db.create_data(&path("items"), json!({})).unwrap();
+    assert_eq!(persister.flush(&db).await, Ok(true));
+    assert_eq!(persister.load().unwrap(), Some(db.snapshot()));
+    assert_eq!(persister.flush(&db).await, Ok(false));
+    std::fs::remove_file(file).unwrap();
+}
+
+#[test]
+fn db_export_round_trip() {
+    let db = Database::new();
+    db.load(&json!({ "data": { "items|2": [{ "id|+1": 1 }] } }))
+        .unwrap();
+    let record = json!({ "id": 3, "email": "a@b.com", "k|2": "x" });
+    db.restore(HashMap::from([("items".to_string(), json!([record]))]));
+
+    // the exported records are loaded as the literal data
+    let other = Database::new();
+    other.load(&db.export()).unwrap();
+    assert_eq!(other.snapshot(), db.snapshot());
+
+    // reset to the records instead of the templates
+    other.restore(HashMap::from([("items".to_string(), json!([]))]));
+    other.reset(Some("items")).unwrap();
+    assert_eq!(other.snapshot()["items"], json!([record]));
+
+    assert!(other.load(&json!({ "records": [] })).is_err());
+}
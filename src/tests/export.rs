@@ -0,0 +1,44 @@
+use crate::export::{self, ExportFormat};
+use serde_json::json;
+use std::collections::HashMap;
+
+#[test]
+fn export_generate() {
+    let json = json!({ "data": { "users|10": [{ "id|+1": 1 }], "posts|10": [{ "id|+1": 1 }] } });
+    let counts = HashMap::from([("users".to_string(), 3)]);
+
+    let collections = export::generate(&json, &["users".to_string()], &counts, None).unwrap();
+    assert_eq!(
+        collections,
+        vec![(
+            "users".to_string(),
+            json!([{ "id": 1 }, { "id": 2 }, { "id": 3 }])
+        )]
+    );
+
+    assert!(export::generate(&json, &["nope".to_string()], &HashMap::new(), None).is_err());
+}
+
+#[test]
+fn export_format() {
+    let data = json!([
+        { "id": 1, "name": "a,\"b\"", "tags": ["x"] },
+        { "id": 2, "name": "it's", "ok": true }
+    ]);
+
+    assert_eq!(
+        ExportFormat::Ndjson.format("users", &data),
+        "{\"id\":1,\"name\":\"a,\\\"b\\\"\",\"tags\":[\"x\"]}\n{\"id\":2,\"name\":\"it's\",\"ok\":true}\n"
+    );
+
+    assert_eq!(
+        ExportFormat::Csv.format("users", &data),
+        "id,name,tags,ok\n1,\"a,\"\"b\"\"\",\"[\"\"x\"\"]\",\n2,it's,,true\n"
+    );
+
+    assert_eq!(
+        ExportFormat::Sql.format("users", &data),
+        "INSERT INTO \"users\" (\"id\", \"name\", \"tags\", \"ok\") VALUES (1, 'a,\"b\"', '[\"x\"]', NULL);\n\
+         INSERT INTO \"users\" (\"id\", \"name\", \"tags\", \"ok\") VALUES (2, 'it''s', NULL, TRUE);\n"
+    );
+}
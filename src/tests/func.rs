@@ -5,7 +5,7 @@ use serde_json::Value;
 /// name: { "name": "@name" }
 #[test]
 fn fun_name() {
-    let (name, value) = template::gen_data("name", &json!("@name"));
+    let (name, value) = template::gen_data("name", &json!("@name")).unwrap();
 
     assert_eq!(name, "name");
     if let Value::String(v) = value {
@@ -15,55 +15,55 @@ fn fun_name() {
 
 #[test]
 fn fun_word() {
-    let (name, value) = template::gen_data("name", &json!("@word"));
+    let (name, value) = template::gen_data("name", &json!("@word")).unwrap();
 
     println!("{}", value);
     assert_eq!(name, "name");
     if let Value::String(v) = value {
-        return assert!(v.len() > 0);
+        return assert!(!v.is_empty());
     }
-    assert!(false);
+    panic!("unexpected value");
 }
 
 #[test]
 fn fun_word_with_one_param() {
-    let (name, value) = template::gen_data("name", &json!("@word(10)"));
+    let (name, value) = template::gen_data("name", &json!("@word(10)")).unwrap();
 
     println!("{}", value);
     assert_eq!(name, "name");
     if let Value::String(v) = value {
         return assert!(v.len() == 10);
     }
-    assert!(false);
+    panic!("unexpected value");
 }
 
 #[test]
 fn fun_word_with_min_max_params() {
-    let (name, value) = template::gen_data("name", &json!("@word(5, 10)"));
+    let (name, value) = template::gen_data("name", &json!("@word(5, 10)")).unwrap();
 
     println!("{}", value);
     assert_eq!(name, "name");
     if let Value::String(v) = value {
         return assert!(v.len() >= 5 && v.len() <= 10);
     }
-    assert!(false);
+    panic!("unexpected value");
 }
 
 #[test]
 fn fun_sentence() {
-    let (name, value) = template::gen_data("name", &json!("@sentence"));
+    let (name, value) = template::gen_data("name", &json!("@sentence")).unwrap();
 
     println!("{}", value);
     assert_eq!(name, "name");
     if let Value::String(v) = value {
         return assert!(v.contains(".") && v.contains(" "));
     }
-    assert!(false);
+    panic!("unexpected value");
 }
 
 #[test]
 fn fun_sentence_with_one_param() {
-    let (name, value) = template::gen_data("name", &json!("@sentence(4)"));
+    let (name, value) = template::gen_data("name", &json!("@sentence(4)")).unwrap();
 
     println!("{}", value);
     assert_eq!(name, "name");
@@ -78,7 +78,7 @@ fn fun_sentence_with_one_param() {
 
 #[test]
 fn fun_sentence_with_min_max_params() {
-    let (name, value) = template::gen_data("name", &json!("@sentence(4, 10)"));
+    let (name, value) = template::gen_data("name", &json!("@sentence(4, 10)")).unwrap();
 
     println!("{}", value);
     assert_eq!(name, "name");
@@ -93,25 +93,25 @@ fn fun_sentence_with_min_max_params() {
 
 #[test]
 fn fun_paragraph() {
-    let (name, value) = template::gen_data("name", &json!("@paragraph"));
+    let (name, value) = template::gen_data("name", &json!("@paragraph")).unwrap();
 
     println!("{}", value);
     assert_eq!(name, "name");
     if let Value::String(v) = value {
         let arr: Vec<&str> = v.split(".").collect();
         println!("{:?}", arr);
-        assert!(arr.len() > 0);
+        assert!(!arr.is_empty());
     }
 }
 
 #[test]
 fn fun_paragraph_with_one_param() {
-    let (name, value) = template::gen_data("name", &json!("@paragraph(5)"));
+    let (name, value) = template::gen_data("name", &json!("@paragraph(5)")).unwrap();
 
     println!("{}", value);
     assert_eq!(name, "name");
     if let Value::String(v) = value {
-        let arr: Vec<&str> = v.split(".").filter(|item| *item != "").collect();
+        let arr: Vec<&str> = v.split(".").filter(|item| !item.is_empty()).collect();
         println!("{:?}", arr);
         assert!(arr.len() == 5);
     }
@@ -119,12 +119,12 @@ fn fun_paragraph_with_one_param() {
 
 #[test]
 fn fun_paragraph_with_min_max_params() {
-    let (name, value) = template::gen_data("name", &json!("@paragraph(5, 10)"));
+    let (name, value) = template::gen_data("name", &json!("@paragraph(5, 10)")).unwrap();
 
     println!("{}", value);
     assert_eq!(name, "name");
     if let Value::String(v) = value {
-        let arr: Vec<&str> = v.split(".").filter(|item| *item != "").collect();
+        let arr: Vec<&str> = v.split(".").filter(|item| !item.is_empty()).collect();
         println!("{:?}", arr);
         assert!(arr.len() >= 5 && arr.len() <= 10);
     }
@@ -0,0 +1,55 @@
+use crate::listen::Listen;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
+fn tcp(addr: &str) -> Listen {
+    Listen::Tcp(addr.parse::<SocketAddr>().unwrap())
+}
+
+#[test]
+fn listen_ip_with_default_port() {
+    assert_eq!(Listen::parse("0.0.0.0", 8080).unwrap(), tcp("0.0.0.0:8080"));
+    assert_eq!(Listen::parse("::1", 8080).unwrap(), tcp("[::1]:8080"));
+    assert_eq!(Listen::parse("[::]", 8080).unwrap(), tcp("[::]:8080"));
+}
+
+#[test]
+fn listen_ip_with_port() {
+    assert_eq!(
+        Listen::parse("127.0.0.1:3000", 8080).unwrap(),
+        tcp("127.0.0.1:3000")
+    );
+    assert_eq!(
+        Listen::parse("[::1]:3000", 8080).unwrap(),
+        tcp("[::1]:3000")
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn listen_unix() {
+    assert_eq!(
+        Listen::parse("unix:/tmp/mock.sock", 8080).unwrap(),
+        Listen::Unix(PathBuf::from("/tmp/mock.sock"))
+    );
+    assert!(Listen::parse("unix:", 8080).is_err());
+}
+
+#[test]
+fn listen_invalid() {
+    assert!(Listen::parse("localhost:abc", 8080).is_err());
+    assert!(Listen::parse("", 8080).is_err());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn listen_unix_keeps_file() {
+    let file = std::env::temp_dir().join(format!("mock-server-{}.json", uuid::Uuid::new_v4()));
+    std::fs::write(&file, "{}").unwrap();
+
+    // the path which is not a socket is never removed
+    let listen = Listen::Unix(file.clone());
+    assert!(listen.serve(axum::Router::new()).await.is_err());
+    assert_eq!(std::fs::read_to_string(&file).unwrap(), "{}");
+    std::fs::remove_file(file).unwrap();
+}
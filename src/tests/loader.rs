@@ -0,0 +1,164 @@
+use crate::loader::{self, Format};
+use serde_json::json;
+use std::path::Path;
+
+#[test]
+fn loader_format_from_path() {
+    assert_eq!(Format::from_path(Path::new("db.json")), Format::Json);
+    assert_eq!(Format::from_path(Path::new("db.JSON5")), Format::Json5);
+    assert_eq!(Format::from_path(Path::new("db.yml")), Format::Yaml);
+    assert_eq!(Format::from_path(Path::new("db.yaml")), Format::Yaml);
+    assert_eq!(Format::from_path(Path::new("db.toml")), Format::Toml);
+    assert_eq!(Format::from_path(Path::new("db")), Format::Json);
+}
+
+#[test]
+fn loader_same_value() {
+    let expect = json!({
+        "config": { "routing": { "/a/:id": { "to": "/api/data/:id", "rules": [{ "key": "id", "match": "\\d+", "message": "id" }] } } },
+        "data": { "data|10": [{ "id|+1": 1, "name": "@name" }] }
+    });
+
+    let yaml = r#"
+config:
+  routing:
+    /a/:id:
+      to: /api/data/:id
+      # no escaping for the regex
+      rules:
+        - { key: id, match: '\d+', message: id }
+data:
+  data|10:
+    - id|+1: 1
+      name: "@name"
+"#;
+    assert_eq!(Format::Yaml.parse(yaml).unwrap(), expect);
+
+    let toml = r#"
+[config.routing."/a/:id"]
+to = "/api/data/:id"
+# no escaping for the regex
+rules = [{ key = "id", match = '\d+', message = "id" }]
+
+[data]
+"data|10" = [{ "id|+1" = 1, name = "@name" }]
+"#;
+    assert_eq!(Format::Toml.parse(toml).unwrap(), expect);
+
+    let json5 = r#"{
+  // comments and trailing commas
+  config: { routing: { "/a/:id": { to: "/api/data/:id", rules: [{ key: "id", match: "\\d+", message: "id" },], }, }, },
+  data: { "data|10": [{ "id|+1": 1, name: "@name", }], },
+}"#;
+    assert_eq!(Format::Json5.parse(json5).unwrap(), expect);
+}
+
+#[test]
+fn loader_invalid() {
+    assert!(Format::Json.parse("{ // comment\n }").is_err());
+    assert!(Format::Yaml.parse("a: [").is_err());
+}
+
+fn temp_dir() -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("mock-server-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn loader_dir() {
+    let dir = temp_dir();
+    std::fs::write(
+        dir.join("a.json"),
+        r#"{ "config": { "routing": { "/a": { "to": "/api/a" } } }, "data": { "a|3": [{ "id|+1": 1 }] } }"#,
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("b.yaml"),
+        "config:\n  wrapping:\n    ok: { data: $data }\ndata:\n  b: []\nfile:\n  f: ./f.txt\n",
+    )
+    .unwrap();
+    std::fs::write(dir.join("readme.md"), "not config").unwrap();
+
+    let loaded = loader::load(dir.to_str().unwrap()).unwrap();
+    assert_eq!(
+        loaded.json,
+        json!({
+            "config": {
+                "routing": { "/a": { "to": "/api/a" } },
+                "wrapping": { "ok": { "data": "$data" } }
+            },
+            "data": { "a|3": [{ "id|+1": 1 }], "b": [] },
+            "file": { "f": "./f.txt" }
+        })
+    );
+    assert_eq!(loaded.paths.len(), 3);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn loader_include() {
+    let dir = temp_dir();
+    std::fs::create_dir_all(dir.join("sub")).unwrap();
+    std::fs::write(
+        dir.join("main.json"),
+        r#"{ "include": ["./sub/users.json", "./main.json"], "data": { "posts": [] } }"#,
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("sub/users.json"),
+        r#"{ "data": { "users|2": [{ "id|+1": 1 }] } }"#,
+    )
+    .unwrap();
+
+    let loaded = loader::load(dir.join("main.json").to_str().unwrap()).unwrap();
+    assert_eq!(
+        loaded.json,
+        json!({ "data": { "posts": [], "users|2": [{ "id|+1": 1 }] } })
+    );
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn loader_conflict() {
+    let dir = temp_dir();
+    std::fs::write(dir.join("a.json"), r#"{ "data": { "users|10": [] } }"#).unwrap();
+    std::fs::write(dir.join("b.json"), r#"{ "data": { "users|20": [] } }"#).unwrap();
+
+    let err = loader::load(dir.to_str().unwrap()).unwrap_err();
+    assert!(err.contains("collection 'users' is already defined"));
+
+    std::fs::write(
+        dir.join("b.json"),
+        r#"{ "config": { "routing": { "/a": { "to": "/api/users" } } } }"#,
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("c.json"),
+        r#"{ "config": { "routing": { "/a": { "to": "/api/users" } } } }"#,
+    )
+    .unwrap();
+    let err = loader::load(dir.to_str().unwrap()).unwrap_err();
+    assert!(err.contains("route '/a' is already defined"));
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn loader_invalid_rule() {
+    let dir = temp_dir();
+    std::fs::write(
+        dir.join("a.json"),
+        r#"{ "config": { "seed": 1 }, "data": { "v|3-1": [], "x|99999999999999999999999": 1 } }"#,
+    )
+    .unwrap();
+
+    // the invalid rules are merged and left to the check
+    let loaded = loader::load(dir.to_str().unwrap()).unwrap();
+    assert_eq!(loaded.json["data"]["v|3-1"], json!([]));
+
+    std::fs::write(dir.join("b.json"), r#"{ "data": { "v": [] } }"#).unwrap();
+    let err = loader::load(dir.to_str().unwrap()).unwrap_err();
+    assert!(err.contains("collection 'v' is already defined"));
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -1,4 +1,13 @@
+mod aggregate;
+mod app;
 mod array;
 mod basic;
+mod check;
+mod db;
+mod export;
 mod func;
+mod listen;
+mod loader;
 mod object;
+mod query;
+mod tls;
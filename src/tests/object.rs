@@ -5,16 +5,16 @@ use serde_json::Value;
 /// name: { "test|3-10": 6 }
 #[test]
 fn object_gen() {
-    let (name, value) = template::gen_data("name", &json!({ "test|3-10": 6 }));
+    let (name, value) = template::gen_data("name", &json!({ "test|3-10": 6 })).unwrap();
 
     assert_eq!(name, "name");
 
     if let Value::Object(v) = value {
         if let Value::Number(number) = &v["test"] {
             let num = number.as_i64().unwrap();
-            return assert!(num >= 3 && num <= 10);
+            return assert!((3..=10).contains(&num));
         }
     }
 
-    assert!(false);
+    panic!("unexpected value");
 }
@@ -0,0 +1,587 @@
+use crate::Database;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+fn users() -> Database {
+    let db = Database::new();
+    db.load(&json!({
+        "data": {
+            "users": [
+                { "id": 1, "name": "a", "status": "active", "admin": true, "score": 1.5, "tags": ["x", "y"] },
+                { "id": 2, "name": "b", "status": "banned", "admin": false, "score": 2, "tags": ["y"] },
+                { "id": 3, "name": "c", "status": "active", "admin": false, "score": 3 },
+                { "id": 4, "name": "d", "status": "pending", "admin": false }
+            ]
+        }
+    }))
+    .unwrap();
+    db
+}
+
+fn query(db: &Database, query: &[(&str, &str)]) -> Result<Value, String> {
+    query_by(db, "users", query)
+}
+
+fn query_by(db: &Database, data: &str, query: &[(&str, &str)]) -> Result<Value, String> {
+    let path = HashMap::from([("data".to_string(), data.to_string())]);
+    let query: Vec<(String, String)> = query
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    db.query_data(&path, &query).map(|(value, _)| value)
+}
+
+fn ids(value: &Value) -> Vec<i64> {
+    value
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|v| v["id"].as_i64().unwrap())
+        .collect()
+}
+
+#[test]
+fn query_filter_string() {
+    let db = users();
+    let value = query(&db, &[("status", "active")]).unwrap();
+    assert_eq!(ids(&value), [1, 3]);
+}
+
+#[test]
+fn query_filter_typed() {
+    let db = users();
+    assert_eq!(ids(&query(&db, &[("id", "2")]).unwrap()), [2]);
+    assert_eq!(ids(&query(&db, &[("admin", "true")]).unwrap()), [1]);
+    assert_eq!(ids(&query(&db, &[("score", "1.5")]).unwrap()), [1]);
+    assert_eq!(ids(&query(&db, &[("score", "2.0")]).unwrap()), [2]);
+    assert!(ids(&query(&db, &[("admin", "yes")]).unwrap()).is_empty());
+}
+
+#[test]
+fn query_filter_underscore_key() {
+    let db = Database::new();
+    db.load(&json!({ "data": { "docs": [{ "id": 1, "_id": "a" }, { "id": 2, "_id": "b" }] } }))
+        .unwrap();
+
+    // the key starting with `_` is a filter only if the items have the field
+    assert_eq!(ids(&query_by(&db, "docs", &[("_id", "b")]).unwrap()), [2]);
+    assert_eq!(
+        ids(&query_by(&db, "docs", &[("_id_ne", "b")]).unwrap()),
+        [1]
+    );
+    assert_eq!(
+        ids(&query_by(&db, "docs", &[("_other", "x")]).unwrap()),
+        [1, 2]
+    );
+    assert_eq!(
+        ids(&query_by(&db, "docs", &[("_", "12345")]).unwrap()),
+        [1, 2]
+    );
+}
+
+#[test]
+fn query_filter_operator_like_field() {
+    let db = Database::new();
+    let docs = json!([
+        { "id": 1, "sign_in": true, "sign": "a" },
+        { "id": 2, "sign_in": false, "sign": "b" }
+    ]);
+    db.load(&json!({ "data": { "docs": docs } })).unwrap();
+
+    // the field with an operator suffix is matched as it is
+    assert_eq!(
+        ids(&query_by(&db, "docs", &[("sign_in", "true")]).unwrap()),
+        [1]
+    );
+    assert_eq!(
+        ids(&query_by(&db, "docs", &[("sign_ne", "a")]).unwrap()),
+        [2]
+    );
+}
+
+#[test]
+fn query_filter_any_of() {
+    let db = users();
+    let value = query(&db, &[("status", "banned"), ("status", "pending")]).unwrap();
+    assert_eq!(ids(&value), [2, 4]);
+
+    let value = query(&db, &[("status", "active"), ("admin", "false")]).unwrap();
+    assert_eq!(ids(&value), [3]);
+}
+
+#[test]
+fn query_filter_range() {
+    let db = users();
+    assert_eq!(ids(&query(&db, &[("score_gte", "2")]).unwrap()), [2, 3]);
+    assert_eq!(ids(&query(&db, &[("score_gt", "1.5")]).unwrap()), [2, 3]);
+    assert_eq!(ids(&query(&db, &[("id_lt", "3")]).unwrap()), [1, 2]);
+
+    let value = query(&db, &[("score_gte", "1.6"), ("score_lte", "2")]).unwrap();
+    assert_eq!(ids(&value), [2]);
+}
+
+#[test]
+fn query_filter_ne() {
+    let db = users();
+    let value = query(&db, &[("status_ne", "active"), ("status_ne", "banned")]).unwrap();
+    assert_eq!(ids(&value), [4]);
+
+    // the missing field is not equal
+    assert_eq!(ids(&query(&db, &[("score_ne", "1.5")]).unwrap()), [2, 3, 4]);
+}
+
+#[test]
+fn query_filter_like() {
+    let db = users();
+    assert_eq!(
+        ids(&query(&db, &[("status_like", "^(ban|pen)")]).unwrap()),
+        [2, 4]
+    );
+    assert!(query(&db, &[("name_like", "(")]).is_err());
+}
+
+#[test]
+fn query_filter_in() {
+    let db = users();
+    assert_eq!(ids(&query(&db, &[("id_in", "1,3,5")]).unwrap()), [1, 3]);
+    assert_eq!(
+        ids(&query(&db, &[("id_in", "1"), ("id_in", "4")]).unwrap()),
+        [1, 4]
+    );
+}
+
+#[test]
+fn query_filter_contains() {
+    let db = users();
+    assert_eq!(ids(&query(&db, &[("tags_contains", "y")]).unwrap()), [1, 2]);
+    assert_eq!(ids(&query(&db, &[("tags_contains", "x")]).unwrap()), [1]);
+    assert_eq!(
+        ids(&query(&db, &[("status_contains", "ct")]).unwrap()),
+        [1, 3]
+    );
+}
+
+#[test]
+fn query_nested_path() {
+    let db = Database::new();
+    db.load(&json!({
+        "data": {
+            "users": [
+                { "id": 1, "address": { "city": "Paris" }, "owner": { "profile": { "age": 30 } } },
+                { "id": 2, "address": { "city": "Rome" }, "owner": { "profile": { "age": 20 } } },
+                { "id": 3, "address": { "city": "Oslo" }, "owner": { "profile": { "age": 40 } } }
+            ]
+        }
+    }))
+    .unwrap();
+
+    assert_eq!(ids(&query(&db, &[("address.city", "Rome")]).unwrap()), [2]);
+    assert_eq!(
+        ids(&query(&db, &[("/owner/profile/age_gte", "30")]).unwrap()),
+        [1, 3]
+    );
+    assert_eq!(ids(&query(&db, &[("_q", "^Os")]).unwrap()), [3]);
+
+    let value = query(&db, &[("_sort", "owner.profile.age")]).unwrap();
+    assert_eq!(ids(&value), [2, 1, 3]);
+    let value = query(&db, &[("_sort", "/address/city"), ("_order", "desc")]).unwrap();
+    assert_eq!(ids(&value), [2, 1, 3]);
+}
+
+#[test]
+fn query_sort_multi_keys() {
+    let db = users();
+    let value = query(&db, &[("_sort", "status,-id")]).unwrap();
+    assert_eq!(ids(&value), [3, 1, 2, 4]);
+
+    let value = query(&db, &[("_sort", "admin,id"), ("_order", "asc,desc")]).unwrap();
+    assert_eq!(ids(&value), [4, 3, 2, 1]);
+
+    // the same keys keep the original order
+    let value = query(&db, &[("_sort", "admin")]).unwrap();
+    assert_eq!(ids(&value), [2, 3, 4, 1]);
+
+    assert!(query(&db, &[("_sort", "id"), ("_order", "up")]).is_err());
+}
+
+#[test]
+fn query_sort_nulls() {
+    let db = users();
+    let value = query(&db, &[("_sort", "-score")]).unwrap();
+    assert_eq!(ids(&value), [3, 2, 1, 4]);
+
+    let value = query(&db, &[("_sort", "score"), ("_nulls", "first")]).unwrap();
+    assert_eq!(ids(&value), [4, 1, 2, 3]);
+}
+
+#[test]
+fn query_cmp_types() {
+    use crate::util::cmp;
+    use std::cmp::Ordering;
+
+    let mut list = vec![
+        json!({ "a": 1 }),
+        json!([1, 2]),
+        json!("b"),
+        json!(2.5),
+        json!(2),
+        json!(true),
+        json!(null),
+    ];
+    list.sort_by(cmp);
+    assert_eq!(
+        list,
+        [
+            json!(null),
+            json!(true),
+            json!(2),
+            json!(2.5),
+            json!("b"),
+            json!([1, 2]),
+            json!({ "a": 1 })
+        ]
+    );
+    assert_eq!(cmp(&json!([1, 2]), &json!([1, 2, 0])), Ordering::Less);
+    assert_eq!(cmp(&json!(u64::MAX), &json!(-1)), Ordering::Greater);
+}
+
+fn pages() -> Database {
+    let db = Database::new();
+    db.load(&json!({
+        "config": {
+            "wrapping": {
+                "pagination": {
+                    "page": "$page",
+                    "total": "$total",
+                    "items": "$items",
+                    "next": "$nextCursor"
+                }
+            }
+        },
+        "data": { "users|5": [{ "id|+1": 1 }] }
+    }))
+    .unwrap();
+    db
+}
+
+#[test]
+fn query_page() {
+    let db = pages();
+    let value = query(&db, &[("_page", "2"), ("_size", "2")]).unwrap();
+    assert_eq!(value["page"], 2);
+    assert_eq!(value["total"], 5);
+    assert_eq!(ids(&value["items"]), [3, 4]);
+    assert_eq!(value["next"], Value::Null);
+}
+
+#[test]
+fn query_page_invalid() {
+    let db = pages();
+    assert!(query(&db, &[("_page", "0")]).is_err());
+    assert!(query(&db, &[("_page", "a")]).is_err());
+    assert!(query(&db, &[("_size", "-1")]).is_err());
+    assert!(query(&db, &[("_limit", "0")]).is_err());
+    assert!(query(&db, &[("_cursor", "abc")]).is_err());
+
+    // the huge numbers are out of the range instead of overflow
+    let max = usize::MAX.to_string();
+    let value = query(&db, &[("_page", &max), ("_size", "2")]).unwrap();
+    assert!(ids(&value["items"]).is_empty());
+    let value = query(&db, &[("_start", "1"), ("_limit", &max)]).unwrap();
+    assert_eq!(ids(&value), [2, 3, 4, 5]);
+    let cursor = crate::models::encode_cursor(1);
+    let value = query(&db, &[("_cursor", &cursor), ("_limit", &max)]).unwrap();
+    assert!(value["next"].is_null());
+}
+
+#[test]
+fn query_slice() {
+    let db = pages();
+    assert_eq!(
+        ids(&query(&db, &[("_start", "1"), ("_end", "3")]).unwrap()),
+        [2, 3]
+    );
+    assert_eq!(
+        ids(&query(&db, &[("_start", "3"), ("_limit", "5")]).unwrap()),
+        [4, 5]
+    );
+    assert_eq!(ids(&query(&db, &[("_limit", "2")]).unwrap()), [1, 2]);
+    assert!(ids(&query(&db, &[("_start", "3"), ("_end", "1")]).unwrap()).is_empty());
+}
+
+#[test]
+fn query_cursor() {
+    let db = pages();
+    let mut cursor = String::new();
+    let mut list = Vec::new();
+    loop {
+        let value = query(&db, &[("_cursor", &cursor), ("_limit", "2")]).unwrap();
+        assert_eq!(value["total"], 5);
+        list.extend(ids(&value["items"]));
+        match value["next"].as_str() {
+            Some(next) => cursor = next.to_string(),
+            None => break,
+        }
+    }
+    assert_eq!(list, [1, 2, 3, 4, 5]);
+}
+
+fn page_response(db: &Database, uri: &str) -> axum::response::Response {
+    let uri: axum::http::Uri = uri.parse().unwrap();
+    let query: Vec<(String, String)> = serde_urlencoded::from_str(uri.query().unwrap()).unwrap();
+    let path = HashMap::from([("data".to_string(), "users".to_string())]);
+    crate::service::query_page(&path, &query, db, &uri, "_page")
+}
+
+#[test]
+fn query_page_headers() {
+    let db = pages();
+    let res = page_response(&db, "/api/users?_size=2&_page=2&id_ne=9");
+    let headers = res.headers();
+    assert_eq!(headers["x-total-count"], "5");
+    assert_eq!(
+        headers["link"],
+        "</api/users?_size=2&_page=1&id_ne=9>; rel=\"first\", \
+         </api/users?_size=2&_page=1&id_ne=9>; rel=\"prev\", \
+         </api/users?_size=2&_page=3&id_ne=9>; rel=\"next\", \
+         </api/users?_size=2&_page=3&id_ne=9>; rel=\"last\""
+    );
+
+    let res = page_response(&db, "/api/users?_start=1");
+    assert!(res.headers().get("x-total-count").is_none());
+}
+
+#[test]
+fn query_page_headers_only() {
+    let db = Database::new();
+    db.load(&json!({
+        "config": { "page_headers": "headers" },
+        "data": { "users|3": [{ "id|+1": 1 }] }
+    }))
+    .unwrap();
+    let value = query(&db, &[("_page", "1"), ("_size", "2")]).unwrap();
+    assert_eq!(ids(&value), [1, 2]);
+
+    let res = page_response(&db, "/api/users?_page=1&_size=2");
+    assert_eq!(res.headers()["x-total-count"], "3");
+
+    db.load(&json!({
+        "config": { "page_headers": "body" },
+        "data": { "users|3": [{ "id|+1": 1 }] }
+    }))
+    .unwrap();
+    let res = page_response(&db, "/api/users?_page=1&_size=2");
+    assert!(res.headers().get("link").is_none());
+}
+
+#[test]
+fn query_search() {
+    let db = Database::new();
+    db.load(&json!({
+        "data": {
+            "users": [
+                { "id": 1, "name": "John", "email": "john at a.com", "phone": 5551234 },
+                { "id": 2, "name": "Mary", "email": "mary at b.com", "tags": ["vip", "new"] },
+                { "id": 3, "name": "Bob", "email": "bob at a.com", "address": { "city": "john town" } }
+            ]
+        }
+    }))
+    .unwrap();
+
+    assert_eq!(ids(&query(&db, &[("_q", "John")]).unwrap()), [1]);
+    assert_eq!(ids(&query(&db, &[("_q", "555")]).unwrap()), [1]);
+    assert_eq!(ids(&query(&db, &[("_q", "^vip$")]).unwrap()), [2]);
+    assert_eq!(ids(&query(&db, &[("_q", "town")]).unwrap()), [3]);
+
+    let value = query(&db, &[("_q", "john"), ("_q_case", "insensitive")]).unwrap();
+    assert_eq!(ids(&value), [1, 3]);
+
+    let value = query(&db, &[("_q", "a.com"), ("_q_mode", "plain")]).unwrap();
+    assert_eq!(ids(&value), [1, 3]);
+    assert_eq!(ids(&query(&db, &[("_q", "j.hn")]).unwrap()), [1, 3]);
+    assert!(ids(&query(&db, &[("_q", "j.hn"), ("_q_mode", "plain")]).unwrap()).is_empty());
+
+    let value = query(&db, &[("_q", "john"), ("_q_fields", "name,address.city")]).unwrap();
+    assert_eq!(ids(&value), [3]);
+
+    assert!(query(&db, &[("_q", "[")]).is_err());
+    assert!(query(&db, &[("_q", "a"), ("_q_mode", "fuzzy")]).is_err());
+}
+
+#[test]
+fn query_projection() {
+    let db = Database::new();
+    db.load(&json!({
+        "config": { "wrapping": { "pagination": { "items": "$items" } } },
+        "data": {
+            "users": [
+                { "id": 1, "name": "a", "bio": "x", "address": { "city": "Paris", "zip": "1" } },
+                { "id": 2, "name": "b", "bio": "y", "tags": [{ "k": 1, "v": 2 }] }
+            ]
+        }
+    }))
+    .unwrap();
+
+    let value = query(&db, &[("_fields", "id,address.city")]).unwrap();
+    assert_eq!(
+        value,
+        json!([{ "id": 1, "address": { "city": "Paris" } }, { "id": 2 }])
+    );
+
+    let value = query(&db, &[("_exclude", "bio,address.zip"), ("id", "1")]).unwrap();
+    assert_eq!(
+        value,
+        json!([{ "id": 1, "name": "a", "address": { "city": "Paris" } }])
+    );
+
+    let value = query(
+        &db,
+        &[("_fields", "tags.k"), ("_page", "2"), ("_size", "1")],
+    )
+    .unwrap();
+    assert_eq!(value, json!({ "items": [{ "tags": [{ "k": 1 }] }] }));
+
+    let path = HashMap::from([
+        ("data".to_string(), "users".to_string()),
+        ("id".to_string(), "1".to_string()),
+    ]);
+    let query = [("_fields".to_string(), "name,/address/zip".to_string())];
+    let value = db.get_data(&path, &query).unwrap();
+    assert_eq!(value, json!({ "name": "a", "address": { "zip": "1" } }));
+}
+
+fn blog() -> Database {
+    let db = Database::new();
+    db.load(&json!({
+        "config": { "relations": { "posts": { "author": "users" } } },
+        "data": {
+            "users": [{ "id": 1, "name": "a" }],
+            "posts": [{ "id": 1, "author": 1 }, { "id": 2, "author": 2 }],
+            "comments": [
+                { "id": 1, "postId": 1 },
+                { "id": 2, "postId": "1" },
+                { "id": 3, "postId": 2 }
+            ],
+            "categories": [{ "id": 1 }],
+            "tags": [{ "id": 1, "categoryId": 1 }]
+        }
+    }))
+    .unwrap();
+    db
+}
+
+#[test]
+fn query_embed() {
+    let db = blog();
+    let value = query_by(&db, "posts", &[("_embed", "comments"), ("id", "1")]).unwrap();
+    assert_eq!(ids(&value[0]["comments"]), [1, 2]);
+
+    let value = query_by(&db, "users", &[("_embed", "posts")]).unwrap();
+    assert_eq!(ids(&value[0]["posts"]), [1]);
+
+    let path = HashMap::from([
+        ("data".to_string(), "posts".to_string()),
+        ("id".to_string(), "2".to_string()),
+    ]);
+    let query = [("_embed".to_string(), "comments".to_string())];
+    let value = db.get_data(&path, &query).unwrap();
+    assert_eq!(ids(&value["comments"]), [3]);
+
+    assert!(query_by(&db, "posts", &[("_embed", "nope")]).is_err());
+}
+
+#[test]
+fn query_expand() {
+    let db = blog();
+    let value = query_by(&db, "comments", &[("_expand", "post")]).unwrap();
+    assert_eq!(
+        value[0],
+        json!({ "id": 1, "post": { "id": 1, "author": 1 } })
+    );
+
+    let value = query_by(&db, "posts", &[("_expand", "author")]).unwrap();
+    assert_eq!(value[0]["author"], json!({ "id": 1, "name": "a" }));
+    assert_eq!(value[1]["author"], Value::Null);
+
+    let value = query_by(&db, "posts", &[("_expand", "user")]).unwrap();
+    assert_eq!(value[0]["user"]["name"], "a");
+
+    let value = query_by(&db, "tags", &[("_expand", "category")]).unwrap();
+    assert_eq!(value[0]["category"], json!({ "id": 1 }));
+
+    assert!(query_by(&db, "comments", &[("_expand", "nope")]).is_err());
+}
+
+fn nested(path: &[&str]) -> HashMap<String, String> {
+    ["data", "id", "child", "child_id"]
+        .iter()
+        .zip(path)
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+#[test]
+fn query_nested_route() {
+    let mut db = blog();
+    let (value, _) = db
+        .query_data(&nested(&["posts", "1", "comments"]), &[])
+        .unwrap();
+    assert_eq!(ids(&value), [1, 2]);
+
+    let query = [("_sort".to_string(), "-id".to_string())];
+    let (value, _) = db
+        .query_data(&nested(&["posts", "1", "comments"]), &query)
+        .unwrap();
+    assert_eq!(ids(&value), [2, 1]);
+
+    // the foreign key from the relations
+    let (value, _) = db
+        .query_data(&nested(&["users", "1", "posts"]), &[])
+        .unwrap();
+    assert_eq!(ids(&value), [1]);
+
+    assert!(db
+        .query_data(&nested(&["posts", "9", "comments"]), &[])
+        .is_err());
+    assert!(db
+        .query_data(&nested(&["posts", "1", "nope"]), &[])
+        .is_err());
+
+    let value = db.get_data(&nested(&["posts", "2", "comments", "3"]), &[]);
+    assert_eq!(value.unwrap()["postId"], 2);
+    assert!(db
+        .get_data(&nested(&["posts", "1", "comments", "3"]), &[])
+        .is_err());
+
+    let value = db
+        .create_data(&nested(&["posts", "2", "comments"]), json!({ "id": 4 }))
+        .unwrap();
+    assert_eq!(value, json!({ "id": 4, "postId": 2 }));
+    let (value, _) = db
+        .query_data(&nested(&["posts", "2", "comments"]), &[])
+        .unwrap();
+    assert_eq!(ids(&value), [3, 4]);
+}
+
+#[test]
+fn query_select() {
+    let db = users();
+    let value = query(&db, &[("_select", "[].name"), ("status", "active")]).unwrap();
+    assert_eq!(value, json!(["a", "c"]));
+
+    let value = query(&db, &[("_select", "$[?(@.admin == true)].id")]).unwrap();
+    assert_eq!(value, json!([1]));
+
+    let value = query(&db, &[("_select", "length(@)")]).unwrap();
+    assert_eq!(value, json!(4));
+
+    let path = HashMap::from([
+        ("data".to_string(), "users".to_string()),
+        ("id".to_string(), "1".to_string()),
+    ]);
+    let query_select = [("_select".to_string(), "tags[0]".to_string())];
+    assert_eq!(db.get_data(&path, &query_select).unwrap(), json!("x"));
+
+    assert!(query(&db, &[("_select", "[.")]).is_err());
+    assert!(query(&db, &[("_select", "$[")]).is_err());
+}
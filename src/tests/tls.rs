@@ -0,0 +1,27 @@
+use crate::tls;
+
+#[tokio::test]
+async fn tls_self_signed() {
+    let dir = std::env::temp_dir().join(format!("mock-server-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let cert = dir.join("cert.pem");
+    let key = dir.join("key.pem");
+    let (cert, key) = (cert.to_str().unwrap(), key.to_str().unwrap());
+
+    tls::self_signed(cert, key).unwrap();
+    let pem = std::fs::read_to_string(cert).unwrap();
+
+    // keep the existing files
+    tls::self_signed(cert, key).unwrap();
+    assert_eq!(std::fs::read_to_string(cert).unwrap(), pem);
+
+    assert!(tls::config(cert, key).await.is_ok());
+
+    // never overwrite the certificate without the key
+    let other = dir.join("other.pem");
+    assert!(tls::self_signed(cert, other.to_str().unwrap()).is_err());
+    assert_eq!(std::fs::read_to_string(cert).unwrap(), pem);
+    assert!(!other.exists());
+    assert!(tls::self_signed(other.to_str().unwrap(), key).is_err());
+    std::fs::remove_dir_all(dir).unwrap();
+}
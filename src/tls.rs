@@ -0,0 +1,34 @@
+use axum_server::tls_rustls::RustlsConfig;
+use std::path::Path;
+
+/// the host names of the self-signed certificate
+const SELF_SIGNED_NAMES: [&str; 3] = ["localhost", "127.0.0.1", "::1"];
+
+/// generate a self-signed certificate for development
+/// the existing certificate files are kept, only one of them existing is an error
+pub fn self_signed(cert: &str, key: &str) -> Result<(), String> {
+    match (Path::new(cert).exists(), Path::new(key).exists()) {
+        (true, true) => return Ok(()),
+        (true, false) => return Err(format!("{}: the certificate exists without the key", cert)),
+        (false, true) => return Err(format!("{}: the key exists without the certificate", key)),
+        (false, false) => {}
+    }
+
+    let names: Vec<String> = SELF_SIGNED_NAMES.iter().map(|s| s.to_string()).collect();
+    let certificate = rcgen::generate_simple_self_signed(names).map_err(|err| err.to_string())?;
+    let cert_pem = certificate.serialize_pem().map_err(|err| err.to_string())?;
+    let key_pem = certificate.serialize_private_key_pem();
+
+    std::fs::write(cert, cert_pem).map_err(|err| format!("{}: {}", cert, err))?;
+    std::fs::write(key, key_pem).map_err(|err| format!("{}: {}", key, err))?;
+    tracing::info!("generated self-signed certificate {} and key {}", cert, key);
+
+    Ok(())
+}
+
+/// load the tls config from the pem files
+pub async fn config(cert: &str, key: &str) -> Result<RustlsConfig, String> {
+    RustlsConfig::from_pem_file(cert, key)
+        .await
+        .map_err(|err| format!("{}, {}: {}", cert, key, err))
+}
@@ -2,15 +2,15 @@ use crate::{
     db::get_config,
     models::{
         Wrapper, WRAP_DATA, WRAP_KEY_ERR, WRAP_KEY_OK, WRAP_MSG, WRAP_PAGE, WRAP_PAGE_ITEMS,
-        WRAP_PAGE_PAGE, WRAP_PAGE_SIZE, WRAP_PAGE_TOTAL,
-    }
+        WRAP_PAGE_NEXT_CURSOR, WRAP_PAGE_PAGE, WRAP_PAGE_SIZE, WRAP_PAGE_TOTAL,
+    },
 };
 use axum::{response::IntoResponse, Json};
 use serde_json::{json, Value};
 use std::cmp::Ordering;
 
-/// sort query by key
-/// for updating uri, the last key should be id
+// sort query by key
+// for updating uri, the last key should be id
 // pub fn sort_keys(query: &HashMap<String, String>) -> Vec<(String, String)> {
 //     tracing::debug!("sort_query_keys: query={:?}", query);
 //     let query_data = query.clone();
@@ -20,7 +20,7 @@ use std::cmp::Ordering;
 //     map
 // }
 
-/// find json by given keys
+// find json by given keys
 // pub fn find_by_data_name<'a>(data: &'a Value, data_name: &str) -> Result<&'a Value, String> {
 //     let json = &data[data_name];
 
@@ -44,33 +44,193 @@ use std::cmp::Ordering;
 //     Err("not found by data_name {data_name}".to_string())
 // }
 
-pub fn cmp(a: &Value, b: &Value) -> Ordering {
-    if a.is_f64() {
-        let a_value = a.as_f64().unwrap_or(0_f64);
-        let b_value = b.as_f64().unwrap_or(0_f64);
-
-        return if a_value > b_value {
-            Ordering::Greater
-        } else if a_value < b_value {
-            Ordering::Less
-        } else {
-            Ordering::Equal
+/// get the value by the dot path such as `address.city` or `tags.0`,
+/// or by the json pointer such as `/address/city`
+pub fn get_path<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    if path.starts_with('/') {
+        return value.pointer(path);
+    }
+
+    // the key with dot itself takes precedence
+    if let Some(v) = value.get(path) {
+        return Some(v);
+    }
+
+    let mut current = value;
+    for key in path.split('.') {
+        current = match current {
+            Value::Object(obj) => obj.get(key)?,
+            Value::Array(arr) => arr.get(key.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+
+    Some(current)
+}
+
+/// split the dot path or json pointer to keys,
+/// the key with dot itself in the value takes precedence
+fn path_keys(value: &Value, path: &str) -> Vec<String> {
+    if let Some(pointer) = path.strip_prefix('/') {
+        return pointer
+            .split('/')
+            .map(|key| key.replace("~1", "/").replace("~0", "~"))
+            .collect();
+    }
+
+    if value.get(path).is_some() {
+        return vec![path.to_string()];
+    }
+
+    path.split('.').map(|key| key.to_string()).collect()
+}
+
+/// pick the value by the path and keep its nested structure,
+/// the array picks the path of every element
+pub fn pick_path(value: &Value, path: &str) -> Option<Value> {
+    fn pick(value: &Value, keys: &[String]) -> Option<Value> {
+        let (key, rest) = match keys.split_first() {
+            Some(v) => v,
+            None => return Some(value.clone()),
         };
-    } else if a.is_i64() {
-        let a_value = a.as_i64().unwrap_or(0_i64);
-        let b_value = b.as_i64().unwrap_or(0_i64);
-        return a_value.cmp(&b_value);
-    } else if a.is_boolean() {
-        let a_value = a.as_bool().unwrap_or(false);
-        let b_value = b.as_bool().unwrap_or(false);
-        return a_value.cmp(&b_value);
-    } else if a.is_string() {
-        let a_value = a.as_str().unwrap_or("");
-        let b_value = b.as_str().unwrap_or("");
-        return a_value.cmp(b_value);
-    }
-
-    Ordering::Equal
+
+        match value {
+            Value::Object(obj) => Some(json!({ key: pick(obj.get(key)?, rest)? })),
+            Value::Array(arr) => Some(Value::Array(
+                arr.iter()
+                    .map(|v| pick(v, keys).unwrap_or_else(|| json!({})))
+                    .collect(),
+            )),
+            _ => None,
+        }
+    }
+
+    pick(value, &path_keys(value, path))
+}
+
+/// merge the source into the target, the objects and arrays are merged recursively
+pub fn merge(target: &mut Value, source: Value) {
+    match (target, source) {
+        (Value::Object(target), Value::Object(source)) => {
+            for (key, value) in source {
+                match target.get_mut(&key) {
+                    Some(v) => merge(v, value),
+                    None => {
+                        target.insert(key, value);
+                    }
+                }
+            }
+        }
+        (Value::Array(target), Value::Array(source)) => {
+            for (t, s) in target.iter_mut().zip(source) {
+                merge(t, s);
+            }
+        }
+        (target, source) => *target = source,
+    }
+}
+
+/// remove the value by the path, the array removes the path of every element
+pub fn remove_path(value: &mut Value, path: &str) {
+    fn remove(value: &mut Value, keys: &[String]) {
+        let (key, rest) = match keys.split_first() {
+            Some(v) => v,
+            None => return,
+        };
+
+        match value {
+            Value::Object(obj) if rest.is_empty() => {
+                obj.remove(key);
+            }
+            Value::Object(obj) => {
+                if let Some(v) = obj.get_mut(key) {
+                    remove(v, rest);
+                }
+            }
+            Value::Array(arr) => arr.iter_mut().for_each(|v| remove(v, keys)),
+            _ => {}
+        }
+    }
+
+    let keys = path_keys(value, path);
+    remove(value, &keys);
+}
+
+/// select the value by the JSONPath which starts with `$`, otherwise by the JMESPath
+pub fn select(value: &Value, expr: &str) -> Result<Value, String> {
+    let err = |err: String| format!("invalid select '{}': {}", expr, err);
+    if expr.starts_with('$') {
+        let values = jsonpath_lib::select(value, expr).map_err(|e| err(e.to_string()))?;
+        return Ok(Value::Array(values.into_iter().cloned().collect()));
+    }
+
+    let expression = jmespath::compile(expr).map_err(|e| err(e.to_string()))?;
+    let ret = expression.search(value).map_err(|e| err(e.to_string()))?;
+    serde_json::to_value(&*ret).map_err(|e| err(e.to_string()))
+}
+
+/// check the select expression is valid
+pub fn check_select(expr: &str) -> Result<(), String> {
+    if expr.starts_with('$') {
+        jsonpath_lib::Compiled::compile(expr).map(|_| ())
+    } else {
+        jmespath::compile(expr)
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// convert the key value to string, the string is not quoted
+pub fn key_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        v => v.to_string(),
+    }
+}
+
+/// compare the json values with a total ordering,
+/// the different types are ordered by null, bool, number, string, array and object
+pub fn cmp(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Null, Value::Null) => Ordering::Equal,
+        (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+        (Value::Number(a), Value::Number(b)) => {
+            if let (Some(a), Some(b)) = (a.as_i64(), b.as_i64()) {
+                return a.cmp(&b);
+            }
+            if let (Some(a), Some(b)) = (a.as_u64(), b.as_u64()) {
+                return a.cmp(&b);
+            }
+            let a = a.as_f64().unwrap_or(0_f64);
+            let b = b.as_f64().unwrap_or(0_f64);
+            a.total_cmp(&b)
+        }
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        (Value::Array(a), Value::Array(b)) => a
+            .iter()
+            .zip(b)
+            .map(|(a, b)| cmp(a, b))
+            .find(|o| o.is_ne())
+            .unwrap_or_else(|| a.len().cmp(&b.len())),
+        (Value::Object(a), Value::Object(b)) => a
+            .iter()
+            .zip(b)
+            .map(|((ak, av), (bk, bv))| ak.cmp(bk).then_with(|| cmp(av, bv)))
+            .find(|o| o.is_ne())
+            .unwrap_or_else(|| a.len().cmp(&b.len())),
+        _ => type_rank(a).cmp(&type_rank(b)),
+    }
+}
+
+fn type_rank(value: &Value) -> u8 {
+    match value {
+        Value::Null => 0,
+        Value::Bool(_) => 1,
+        Value::Number(_) => 2,
+        Value::String(_) => 3,
+        Value::Array(_) => 4,
+        Value::Object(_) => 5,
+    }
 }
 
 /// wrap result by config wrapping
@@ -91,14 +251,12 @@ pub fn wrap_page(
     default_wrap: &Wrapper,
     items: Vec<&Value>,
     total: usize,
-    page: usize,
+    page: Option<usize>,
     size: usize,
+    next_cursor: Option<String>,
     custom_wrap: Option<Wrapper>,
 ) -> Value {
-    let mut wrapper = default_wrap.clone();
-    if custom_wrap.is_some() {
-        wrapper = custom_wrap.unwrap();
-    }
+    let wrapper = custom_wrap.unwrap_or_else(|| default_wrap.clone());
     match wrapper.get(WRAP_PAGE) {
         Some(v) => {
             let mut obj = v.clone();
@@ -119,6 +277,10 @@ pub fn wrap_page(
                 if value.is_string() && value.as_str().unwrap() == WRAP_PAGE_ITEMS {
                     obj[&key] = json!(items);
                 }
+
+                if value.is_string() && value.as_str().unwrap() == WRAP_PAGE_NEXT_CURSOR {
+                    obj[&key] = json!(next_cursor);
+                }
             }
 
             obj
@@ -135,10 +297,7 @@ fn wrap_value(
     custom_wrap: Option<Wrapper>,
 ) -> Result<Json<Value>, String> {
     let config = get_config();
-    let mut wrapper = config.wrapping.clone();
-    if custom_wrap.is_some() {
-        wrapper = custom_wrap.unwrap();
-    }
+    let wrapper = custom_wrap.unwrap_or_else(|| config.wrapping.clone());
 
     match wrapper.get(key) {
         // find wrap data
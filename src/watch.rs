@@ -0,0 +1,67 @@
+use crate::{
+    app::{self, AppRouter},
+    Database,
+};
+use std::{
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+/// the interval to check the config files
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+fn modified(paths: &[PathBuf]) -> Vec<Option<SystemTime>> {
+    paths
+        .iter()
+        .map(|path| std::fs::metadata(path).and_then(|m| m.modified()).ok())
+        .collect()
+}
+
+/// watch the config files and reload the data and routing when they change
+/// the old config keeps running if the new one fails to load
+pub async fn watch(path: String, paths: Vec<PathBuf>, db: Database, router: AppRouter) {
+    let mut paths = paths;
+    let mut last = modified(&paths);
+    let mut interval = tokio::time::interval(WATCH_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        let current = modified(&paths);
+        if current == last {
+            continue;
+        }
+        last = current;
+
+        tracing::info!("config {} changed, reloading", path);
+
+        // generating the data may take a while, keep it off the async workers
+        let result = {
+            let (db, path) = (db.clone(), path.clone());
+            tokio::task::spawn_blocking(move || db.stage(&path))
+                .await
+                .unwrap_or_else(|err| Err(err.to_string()))
+        };
+
+        // build the routes before swapping, so the data and routes are changed together
+        let result =
+            result.and_then(|staged| app::build(&staged.config).map(|routes| (staged, routes)));
+        match result {
+            Ok((staged, routes)) => {
+                // the included files may be changed
+                paths = staged.paths.clone();
+                last = modified(&paths);
+
+                db.apply(staged);
+                router.replace(routes);
+
+                // keep the data changed by the api across the reloads
+                if let Err(err) = db.restore_persisted() {
+                    tracing::error!("failed to load the persisted data: {}", err);
+                }
+                tracing::info!("config {} reloaded", path);
+            }
+            Err(err) => tracing::error!("failed to reload config: {}", err),
+        }
+    }
+}